@@ -0,0 +1,483 @@
+use crate::components::Player;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::reflect::{DynamicEnum, DynamicVariant, FromReflect, TypeInfo, Typed, VariantInfo};
+use std::collections::{HashMap, HashSet};
+
+pub struct ActionPlugin;
+
+impl Plugin for ActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputBindings::load())
+            .init_resource::<ActionState>()
+            .add_systems(PreUpdate, (action_state_system.after(InputSystem),));
+    }
+}
+
+/// Where the bindings are read from, and written to when missing and asked to.
+#[cfg(not(target_arch = "wasm32"))]
+const BINDINGS_PATH: &str = "bindings.cfg";
+
+/// Something the player can do, regardless of the key or button bound to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    RotateLeft,
+    RotateRight,
+    Thrust,
+    Fire,
+    Hyperspace,
+    Export,
+    ToggleInspector,
+    SwitchMode,
+    Pause,
+    ReplayBack,
+    ReplayForward,
+    ReplaySlower,
+    ReplayFaster,
+}
+
+impl Action {
+    pub const ALL: [Action; 13] = [
+        Action::RotateLeft,
+        Action::RotateRight,
+        Action::Thrust,
+        Action::Fire,
+        Action::Hyperspace,
+        Action::Export,
+        Action::ToggleInspector,
+        Action::SwitchMode,
+        Action::Pause,
+        Action::ReplayBack,
+        Action::ReplayForward,
+        Action::ReplaySlower,
+        Action::ReplayFaster,
+    ];
+
+    /// Name used in the bindings file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::RotateLeft => "rotate_left",
+            Action::RotateRight => "rotate_right",
+            Action::Thrust => "thrust",
+            Action::Fire => "fire",
+            Action::Hyperspace => "hyperspace",
+            Action::Export => "export",
+            Action::ToggleInspector => "toggle_inspector",
+            Action::SwitchMode => "switch_mode",
+            Action::Pause => "pause",
+            Action::ReplayBack => "replay_back",
+            Action::ReplayForward => "replay_forward",
+            Action::ReplaySlower => "replay_slower",
+            Action::ReplayFaster => "replay_faster",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+/// Keys and gamepad buttons bound to each action of a single player.
+#[derive(Debug, Clone, Default)]
+pub struct PlayerBindings {
+    pub keys: Vec<(Action, KeyCode)>,
+    pub buttons: Vec<(Action, GamepadButtonType)>,
+    /// Stick axis rotating the ship, pushing right turns clockwise.
+    pub rotate_axis: Option<GamepadAxisType>,
+}
+
+impl PlayerBindings {
+    fn gamepad_defaults() -> Self {
+        Self {
+            keys: vec![],
+            buttons: vec![
+                (Action::RotateLeft, GamepadButtonType::DPadLeft),
+                (Action::RotateRight, GamepadButtonType::DPadRight),
+                (Action::Thrust, GamepadButtonType::DPadUp),
+                (Action::Thrust, GamepadButtonType::RightTrigger2),
+                (Action::Fire, GamepadButtonType::South),
+                (Action::Hyperspace, GamepadButtonType::East),
+                (Action::Export, GamepadButtonType::Select),
+                (Action::SwitchMode, GamepadButtonType::North),
+                (Action::Pause, GamepadButtonType::Start),
+            ],
+            rotate_axis: Some(GamepadAxisType::LeftStickX),
+        }
+    }
+}
+
+/// Bindings of every player, indexed by player id.
+///
+/// The gamepad with id `n` is driven by the button bindings of player `n`.
+#[derive(Resource, Debug, Clone)]
+pub struct InputBindings {
+    pub players: Vec<PlayerBindings>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            players: vec![
+                PlayerBindings {
+                    keys: vec![
+                        (Action::RotateLeft, KeyCode::Left),
+                        (Action::RotateRight, KeyCode::Right),
+                        (Action::Thrust, KeyCode::Up),
+                        (Action::Fire, KeyCode::Space),
+                        (Action::Hyperspace, KeyCode::Down),
+                        (Action::Export, KeyCode::E),
+                        (Action::ToggleInspector, KeyCode::F1),
+                        (Action::SwitchMode, KeyCode::M),
+                        (Action::Pause, KeyCode::P),
+                        (Action::Pause, KeyCode::Escape),
+                        (Action::ReplayBack, KeyCode::Comma),
+                        (Action::ReplayForward, KeyCode::Period),
+                        (Action::ReplaySlower, KeyCode::Minus),
+                        (Action::ReplayFaster, KeyCode::Equals),
+                    ],
+                    ..PlayerBindings::gamepad_defaults()
+                },
+                PlayerBindings {
+                    keys: vec![
+                        (Action::RotateLeft, KeyCode::A),
+                        (Action::RotateRight, KeyCode::D),
+                        (Action::Thrust, KeyCode::W),
+                        (Action::Fire, KeyCode::ShiftLeft),
+                        (Action::Hyperspace, KeyCode::S),
+                    ],
+                    ..PlayerBindings::gamepad_defaults()
+                },
+            ],
+        }
+    }
+}
+
+impl InputBindings {
+    /// Reads the bindings file, falling back to the default bindings if it does not exist.
+    ///
+    /// The missing file is created with the default bindings, as a starting point for editing
+    /// them, when the `RUSTEROIDS_WRITE_BINDINGS` environment variable is set.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load() -> Self {
+        match std::fs::read_to_string(BINDINGS_PATH) {
+            Ok(text) => Self::parse(&text),
+            Err(_) => {
+                let bindings = Self::default();
+                if std::env::var_os("RUSTEROIDS_WRITE_BINDINGS").is_some() {
+                    if let Err(err) = std::fs::write(BINDINGS_PATH, bindings.to_config()) {
+                        warn!("could not write {}: {}", BINDINGS_PATH, err);
+                    }
+                }
+                bindings
+            }
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load() -> Self {
+        Self::default()
+    }
+
+    /// Parses `[player N]` sections made of lines of the form `fire = key:Space, button:South`,
+    /// and `rotate_axis = LeftStickX`. Lines before the first section belong to player 1.
+    ///
+    /// Actions missing from a section have no binding, players without a section keep their
+    /// default bindings, malformed entries are skipped.
+    pub fn parse(text: &str) -> Self {
+        let mut bindings = Self {
+            players: vec![PlayerBindings::default()],
+        };
+        let mut player = 0;
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                let number = section
+                    .trim()
+                    .strip_prefix("player")
+                    .and_then(|n| n.trim().parse::<usize>().ok())
+                    .filter(|n| (1..=Player::MAX).contains(n));
+                match number {
+                    Some(number) => {
+                        player = number - 1;
+                        if bindings.players.len() <= player {
+                            bindings
+                                .players
+                                .resize_with(player + 1, PlayerBindings::default);
+                        }
+                    }
+                    None => warn!("unknown section: {}", line),
+                }
+                continue;
+            }
+
+            let player_bindings = &mut bindings.players[player];
+            let Some((name, inputs)) = line.split_once('=') else {
+                warn!("malformed binding: {}", line);
+                continue;
+            };
+            let name = name.trim();
+
+            if name == "rotate_axis" {
+                player_bindings.rotate_axis = unit_variant(inputs.trim());
+                continue;
+            }
+            let Some(action) = Action::from_name(name) else {
+                warn!("unknown action: {}", name);
+                continue;
+            };
+
+            for input in inputs.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                let parsed = match input.split_once(':') {
+                    Some(("key", key)) => {
+                        unit_variant(key.trim()).map(|key| player_bindings.keys.push((action, key)))
+                    }
+                    Some(("button", button)) => unit_variant(button.trim())
+                        .map(|button| player_bindings.buttons.push((action, button))),
+                    _ => None,
+                };
+                if parsed.is_none() {
+                    warn!("unknown input for {}: {}", name, input);
+                }
+            }
+        }
+
+        let defaults = Self::default().players;
+        let parsed_count = bindings.players.len();
+        bindings
+            .players
+            .extend(defaults.into_iter().skip(parsed_count));
+
+        bindings
+    }
+
+    pub fn to_config(&self) -> String {
+        let mut config = String::from("# action = key:<KeyCode>, button:<GamepadButtonType>\n");
+
+        for (idx, player_bindings) in self.players.iter().enumerate() {
+            config += &format!("\n[player {}]\n", idx + 1);
+
+            for action in Action::ALL {
+                let keys = player_bindings
+                    .keys
+                    .iter()
+                    .filter(|(a, _)| *a == action)
+                    .map(|(_, key)| format!("key:{:?}", key));
+                let buttons = player_bindings
+                    .buttons
+                    .iter()
+                    .filter(|(a, _)| *a == action)
+                    .map(|(_, button)| format!("button:{:?}", button));
+                let inputs = keys.chain(buttons).collect::<Vec<_>>();
+                if !inputs.is_empty() {
+                    config += &format!("{} = {}\n", action.name(), inputs.join(", "));
+                }
+            }
+
+            if let Some(axis) = player_bindings.rotate_axis {
+                config += &format!("rotate_axis = {:?}\n", axis);
+            }
+        }
+
+        config
+    }
+}
+
+/// Builds a field-less enum value from its variant name, e.g. `KeyCode::Space` from `"Space"`.
+fn unit_variant<T: FromReflect + Typed>(name: &str) -> Option<T> {
+    // `from_reflect` panics on unknown variants rather than failing
+    let TypeInfo::Enum(info) = T::type_info() else {
+        return None;
+    };
+    let Some(VariantInfo::Unit(_)) = info.variant(name) else {
+        return None;
+    };
+    T::from_reflect(&DynamicEnum::new(name, DynamicVariant::Unit))
+}
+
+/// State of the actions for the current frame, for each player.
+#[derive(Resource, Debug, Default)]
+pub struct ActionState {
+    pressed: HashSet<(Player, Action)>,
+    just_pressed: HashSet<(Player, Action)>,
+    stick: HashMap<Player, f32>,
+}
+
+impl ActionState {
+    /// Whether any player just pressed the action, for actions not tied to a ship.
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.iter().any(|(_, a)| *a == action)
+    }
+
+    pub fn player_pressed(&self, player: Player, action: Action) -> bool {
+        self.pressed.contains(&(player, action))
+    }
+
+    pub fn player_just_pressed(&self, player: Player, action: Action) -> bool {
+        self.just_pressed.contains(&(player, action))
+    }
+
+    /// Rotation input of a player, between -1 (clockwise) and 1 (counter-clockwise).
+    pub fn rotation(&self, player: Player) -> f32 {
+        let mut rotation = self.stick.get(&player).copied().unwrap_or(0.0);
+        if self.player_pressed(player, Action::RotateLeft) {
+            rotation += 1.0;
+        }
+        if self.player_pressed(player, Action::RotateRight) {
+            rotation -= 1.0;
+        }
+        rotation.clamp(-1.0, 1.0)
+    }
+
+    /// Presses an action from an input source other than the bindings, e.g. touch controls.
+    pub fn press(&mut self, player: Player, action: Action, just_pressed: bool) {
+        self.pressed.insert((player, action));
+        if just_pressed {
+            self.just_pressed.insert((player, action));
+        }
+    }
+}
+
+/// Connected gamepads in the order they go to the players, the first one to player 1 and so on.
+///
+/// Gamepad ids aren't contiguous, e.g. after unplugging and replugging one, so they are only used
+/// for a stable order.
+fn player_gamepads(gamepads: impl Iterator<Item = Gamepad>) -> Vec<Gamepad> {
+    let mut gamepads = gamepads.collect::<Vec<_>>();
+    gamepads.sort_unstable_by_key(|gamepad| gamepad.id);
+    gamepads
+}
+
+pub fn action_state_system(
+    bindings: Res<InputBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    button_input: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut state: ResMut<ActionState>,
+) {
+    state.pressed.clear();
+    state.just_pressed.clear();
+    state.stick.clear();
+
+    let player_gamepads = player_gamepads(gamepads.iter());
+    for (idx, player_bindings) in bindings.players.iter().enumerate() {
+        let player = Player(idx);
+
+        for (action, key) in player_bindings.keys.iter() {
+            if keyboard_input.pressed(*key) {
+                state.pressed.insert((player, *action));
+            }
+            if keyboard_input.just_pressed(*key) {
+                state.just_pressed.insert((player, *action));
+            }
+        }
+
+        if let Some(gamepad) = player_gamepads.get(idx).copied() {
+            for (action, button_type) in player_bindings.buttons.iter() {
+                let button = GamepadButton::new(gamepad, *button_type);
+                if button_input.pressed(button) {
+                    state.pressed.insert((player, *action));
+                }
+                if button_input.just_pressed(button) {
+                    state.just_pressed.insert((player, *action));
+                }
+            }
+
+            if let Some(axis_type) = player_bindings.rotate_axis {
+                let value = axes
+                    .get(GamepadAxis::new(gamepad, axis_type))
+                    .unwrap_or(0.0);
+                *state.stick.entry(player).or_default() -= value;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gamepads_go_to_the_players_in_order() {
+        let gamepads = player_gamepads([Gamepad::new(3), Gamepad::new(1)].into_iter());
+        assert_eq!(gamepads, vec![Gamepad::new(1), Gamepad::new(3)]);
+
+        assert!(player_gamepads(std::iter::empty()).is_empty());
+    }
+
+    #[test]
+    fn config_round_trip() {
+        let defaults = InputBindings::default();
+        let parsed = InputBindings::parse(&defaults.to_config());
+
+        assert_eq!(parsed.players.len(), defaults.players.len());
+        for (parsed, default) in parsed.players.iter().zip(&defaults.players) {
+            assert_eq!(parsed.keys, default.keys);
+            assert_eq!(parsed.buttons, default.buttons);
+            assert_eq!(parsed.rotate_axis, default.rotate_axis);
+        }
+        assert_eq!(parsed.to_config(), defaults.to_config());
+    }
+
+    #[test]
+    fn parse_sections() {
+        let bindings = InputBindings::parse(
+            "fire = key:X\n\
+             [player 2]\n\
+             fire = key:Y, button:West\n\
+             rotate_axis = RightStickX\n",
+        );
+
+        // lines before the first section belong to player 1, which loses its other bindings
+        assert_eq!(bindings.players[0].keys, vec![(Action::Fire, KeyCode::X)]);
+        assert!(bindings.players[0].buttons.is_empty());
+        assert_eq!(bindings.players[0].rotate_axis, None);
+
+        assert_eq!(bindings.players[1].keys, vec![(Action::Fire, KeyCode::Y)]);
+        assert_eq!(
+            bindings.players[1].buttons,
+            vec![(Action::Fire, GamepadButtonType::West)]
+        );
+        assert_eq!(
+            bindings.players[1].rotate_axis,
+            Some(GamepadAxisType::RightStickX)
+        );
+    }
+
+    #[test]
+    fn players_without_section_keep_their_defaults() {
+        let bindings = InputBindings::parse("[player 1]\nfire = key:X\n");
+        let defaults = InputBindings::default();
+
+        assert_eq!(bindings.players.len(), defaults.players.len());
+        assert_eq!(bindings.players[1].keys, defaults.players[1].keys);
+    }
+
+    #[test]
+    fn parse_skips_unknown_entries() {
+        let bindings = InputBindings::parse(
+            "[player 1]\n\
+             fire = key:NotAKey, key:Space, pedal:Left, button:NotAButton\n\
+             teleport = key:T\n\
+             no equal sign\n\
+             rotate_axis = NotAnAxis\n\
+             [player 99]\n\
+             thrust = key:Up\n",
+        );
+
+        // the lines of an unknown section go to the previous one
+        assert_eq!(
+            bindings.players[0].keys,
+            vec![
+                (Action::Fire, KeyCode::Space),
+                (Action::Thrust, KeyCode::Up)
+            ]
+        );
+        assert!(bindings.players[0].buttons.is_empty());
+        assert_eq!(bindings.players[0].rotate_axis, None);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::asteroids::utils::geo_point_to_vec;
+use crate::line_sprite::Shape;
+use bevy::prelude::*;
+use geo::{Area, BooleanOps, Centroid};
+use rand::Rng;
+use std::f32::consts::{FRAC_PI_4, PI, TAU};
+
+/// Distance to which fracture lines extend, well beyond the asteroids' radius of about 1.0.
+const FRACTURE_REACH: f32 = 4.0;
+
+/// Piece cut out of an asteroid, in the asteroid's local coordinates.
+pub struct Fragment {
+    /// Centroid of the piece, which is the origin of its shape and segments.
+    pub center: Vec2,
+    pub shape: Shape,
+    /// Decoration segments of the asteroid falling within the piece.
+    pub segments: Vec<(Vec2, Vec2)>,
+    /// Area of the piece relative to the asteroid's.
+    pub area_ratio: f32,
+}
+
+/// Cuts an asteroid into pieces along fracture lines radiating from the impact point.
+///
+/// `count - 1` lines fan out across the asteroid in the direction of the impact, and one more
+/// points back out of it, so that the asteroid breaks into about `count` pieces (more if its
+/// outline is concave).
+pub fn fracture(
+    shape: &Shape,
+    decorations: &[(Vec2, Vec2)],
+    impact_point: Vec2,
+    impact_direction: Vec2,
+    count: usize,
+    fan_angle: f32,
+    rng: &mut impl Rng,
+) -> Vec<Fragment> {
+    let Some(geo::Geometry::Polygon(polygon)) = shape.as_geometry(&Transform::IDENTITY) else {
+        return vec![];
+    };
+    let total_area = polygon.unsigned_area();
+    if count < 2 || total_area <= 0.0 {
+        return vec![];
+    }
+
+    let base_angle = impact_direction.y.atan2(impact_direction.x);
+    let fan_count = count - 1;
+    let mut angles = (0..fan_count)
+        .map(|idx| {
+            let fan_position = if fan_count > 1 {
+                idx as f32 / (fan_count - 1) as f32 * 2.0 - 1.0
+            } else {
+                0.0
+            };
+            base_angle + fan_position * fan_angle + rng.gen_range(-0.15..0.15)
+        })
+        .collect::<Vec<_>>();
+    angles.push(base_angle + PI);
+    angles.push(angles[0] + TAU);
+
+    let decorations = geo::MultiLineString::new(
+        decorations
+            .iter()
+            .map(|(a, b)| geo::LineString::from(vec![(a.x, a.y), (b.x, b.y)]))
+            .collect(),
+    );
+
+    angles
+        .windows(2)
+        .flat_map(|w| polygon.intersection(&wedge(impact_point, w[0], w[1])))
+        .filter_map(|piece| {
+            let area_ratio = piece.unsigned_area() / total_area;
+            let center = geo_point_to_vec(piece.centroid()?);
+
+            let shape = Shape::from_vertices(
+                piece
+                    .exterior()
+                    .points()
+                    .map(|pt| geo_point_to_vec(pt) - center),
+                true,
+            );
+            let segments = piece
+                .clip(&decorations, false)
+                .into_iter()
+                .flat_map(|line| {
+                    line.lines()
+                        .map(|line| {
+                            (
+                                geo_point_to_vec(line.start_point()) - center,
+                                geo_point_to_vec(line.end_point()) - center,
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+
+            Some(Fragment {
+                center,
+                shape,
+                segments,
+                area_ratio,
+            })
+        })
+        .collect()
+}
+
+/// Angular sector between two fracture lines, reaching beyond the asteroid.
+fn wedge(apex: Vec2, start_angle: f32, end_angle: f32) -> geo::Polygon<f32> {
+    // sample the arc finely enough for the sector to cover the asteroid
+    let steps = ((end_angle - start_angle) / FRAC_PI_4).ceil().max(1.0) as usize;
+
+    let arc = (0..=steps).map(|step| {
+        let angle = start_angle + (end_angle - start_angle) * step as f32 / steps as f32;
+        apex + Vec2::from_angle(angle) * FRACTURE_REACH
+    });
+
+    geo::Polygon::new(
+        std::iter::once(apex)
+            .chain(arc)
+            .map(|pt| (pt.x, pt.y))
+            .collect::<Vec<_>>()
+            .into(),
+        vec![],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::Intersects;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn square() -> Shape {
+        Shape::from_vertices(
+            [
+                Vec2::new(-1.0, -1.0),
+                Vec2::new(1.0, -1.0),
+                Vec2::new(1.0, 1.0),
+                Vec2::new(-1.0, 1.0),
+            ],
+            true,
+        )
+    }
+
+    /// Hits the square on its right side, heading left.
+    fn hit(decorations: &[(Vec2, Vec2)], count: usize, seed: u64) -> Vec<Fragment> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        fracture(
+            &square(),
+            decorations,
+            Vec2::new(1.0, 0.0),
+            Vec2::NEG_X,
+            count,
+            0.2,
+            &mut rng,
+        )
+    }
+
+    #[test]
+    fn pieces_make_up_the_asteroid() {
+        for seed in 0..10 {
+            let fragments = hit(&[], 3, seed);
+            assert_eq!(fragments.len(), 3);
+
+            let total_ratio: f32 = fragments.iter().map(|f| f.area_ratio).sum();
+            assert!((total_ratio - 1.0).abs() < 1e-4, "{}", total_ratio);
+
+            // the area ratio matches the piece, whose centroid is its origin
+            for fragment in fragments {
+                let Some(geo::Geometry::Polygon(piece)) =
+                    fragment.shape.as_geometry(&Transform::IDENTITY)
+                else {
+                    panic!("fragment is not a polygon");
+                };
+                assert!((piece.unsigned_area() / 4.0 - fragment.area_ratio).abs() < 1e-4);
+                let centroid = geo_point_to_vec(piece.centroid().unwrap());
+                assert!(centroid.length() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn nothing_to_fracture() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut fracture_shape =
+            |shape: &Shape, count| fracture(shape, &[], Vec2::X, Vec2::NEG_X, count, 0.2, &mut rng);
+
+        assert!(fracture_shape(&square(), 1).is_empty());
+        assert!(fracture_shape(&square(), 0).is_empty());
+
+        let line = Shape::from_vertices([Vec2::ZERO, Vec2::X], false);
+        assert!(fracture_shape(&line, 3).is_empty());
+
+        let flat = Shape::from_vertices([Vec2::ZERO, Vec2::X, Vec2::new(2.0, 0.0)], true);
+        assert!(fracture_shape(&flat, 3).is_empty());
+    }
+
+    #[test]
+    fn decorations_are_clipped_into_their_piece() {
+        let decorations = [
+            // in the piece above the impact
+            (Vec2::new(0.2, 0.8), Vec2::new(0.4, 0.8)),
+            // across the three pieces, the fan lines reaching at most 0.7 from the axis there
+            (Vec2::new(-0.9, -0.95), Vec2::new(-0.9, 0.95)),
+        ];
+
+        for seed in 0..10 {
+            let fragments = hit(&decorations, 3, seed);
+
+            let mut total_length = 0.0;
+            for fragment in &fragments {
+                let piece = fragment
+                    .shape
+                    .as_geometry(&Transform::from_translation(fragment.center.extend(0.0)))
+                    .unwrap();
+
+                for (a, b) in &fragment.segments {
+                    let (a, b) = (*a + fragment.center, *b + fragment.center);
+                    let mid = (a + b) / 2.0;
+                    assert!(piece.intersects(&geo::Point::new(mid.x, mid.y)));
+                    total_length += a.distance(b);
+                }
+            }
+            assert!((total_length - 2.1).abs() < 1e-4, "{}", total_length);
+
+            // the short segment is whole, in the upper piece
+            let upper = fragments
+                .iter()
+                .find(|fragment| fragment.center.y > 0.3)
+                .unwrap();
+            assert!(upper.segments.iter().any(|(a, b)| {
+                (a.distance(*b) - 0.2).abs() < 1e-4 && (a.y + upper.center.y - 0.8).abs() < 1e-4
+            }));
+        }
+    }
+}
@@ -1,19 +1,27 @@
 mod basic;
+mod fracture;
 mod poly_spin_smaller;
 mod poly_vor_diag;
 mod utils;
 
 use crate::line_sprite::Shape;
 use bevy::prelude::*;
+use rand::rngs::StdRng;
 use rand::Rng;
 use vsvg::{DocumentTrait, LayerTrait, PathTrait};
 
+pub use fracture::fracture;
+
 pub trait AsteroidMaker: Sync + Send {
-    fn poly_and_sketch(&self, category: u8) -> (Vec<vsvg::Point>, vsvg_sketch::Sketch);
+    fn poly_and_sketch(
+        &self,
+        category: u8,
+        rng: &mut StdRng,
+    ) -> (Vec<vsvg::Point>, vsvg_sketch::Sketch);
 
     /// Should be scaled for a radius of approx 1.0.
-    fn shape_and_segments(&self, category: u8) -> (Shape, Vec<(Vec2, Vec2)>) {
-        let (pts, sketch) = self.poly_and_sketch(category);
+    fn shape_and_segments(&self, category: u8, rng: &mut StdRng) -> (Shape, Vec<(Vec2, Vec2)>) {
+        let (pts, sketch) = self.poly_and_sketch(category, rng);
 
         // reasonable tolerance for scale ~1 things
         let tolerance = 0.05;
@@ -52,8 +60,7 @@ pub struct AsteroidMakerRegistry {
 }
 
 impl AsteroidMakerRegistry {
-    pub fn get_random(&self) -> &dyn AsteroidMaker {
-        let rng = &mut rand::thread_rng();
+    pub fn get_random(&self, rng: &mut StdRng) -> &dyn AsteroidMaker {
         let index = rng.gen_range(0..self.makers.len());
         self.makers[index].as_ref()
     }
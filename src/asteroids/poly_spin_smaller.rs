@@ -5,6 +5,7 @@ use crate::asteroids::AsteroidMaker;
 use crate::line_sprite::Shape;
 use bevy::math::Vec2;
 use geo::{BooleanOps, Polygon, Rotate, Scale};
+use rand::rngs::StdRng;
 use rand::Rng;
 use rand_distr::{Distribution, Normal};
 use std::f32::consts::PI;
@@ -14,19 +15,17 @@ use vsvg_sketch::Sketch;
 pub struct PolySpinSmallerAsteroid;
 
 impl AsteroidMaker for PolySpinSmallerAsteroid {
-    fn poly_and_sketch(&self, _category: u8) -> (Vec<Point>, Sketch) {
+    fn poly_and_sketch(&self, _category: u8, _rng: &mut StdRng) -> (Vec<Point>, Sketch) {
         todo!("convert to Sketch api")
     }
 
-    fn shape_and_segments(&self, category: u8) -> (Shape, Vec<(Vec2, Vec2)>) {
-        let rng = &mut rand::thread_rng();
-
+    fn shape_and_segments(&self, category: u8, rng: &mut StdRng) -> (Shape, Vec<(Vec2, Vec2)>) {
         let times_to_reduce = 2 + category as usize;
         let scale_amount = 0.53 + 0.08 * category as f32;
 
         let rotation_angle = rng.gen_range(30.0..110.0);
 
-        let base_poly = generate_polygon(1.0, 0.9, 0.13, 18);
+        let base_poly = generate_polygon(1.0, 0.9, 0.13, 18, rng);
 
         let mut prev_poly = base_poly.clone();
         let mut mask: geo::MultiPolygon<f32> = base_poly.clone().into();
@@ -65,16 +64,16 @@ fn generate_polygon(
     mut irregularity: f32,
     mut spikiness: f32,
     num_vertices: usize,
+    rng: &mut impl Rng,
 ) -> Polygon<f32> {
     irregularity *= 2.0 * PI / num_vertices as f32;
     spikiness *= avg_radius;
     let normal = Normal::new(avg_radius, spikiness).unwrap();
 
-    let mut rng = rand::thread_rng();
     let mut points = Vec::new();
     let mut angle = rng.gen_range(0.0..2.0 * PI);
     for _ in 0..num_vertices {
-        let radius = normal.sample(&mut rng).max(0.0).min(2.0 * avg_radius);
+        let radius = normal.sample(rng).max(0.0).min(2.0 * avg_radius);
         let point = (radius * angle.cos(), radius * angle.sin());
         points.push(point);
         angle += irregularity;
@@ -5,6 +5,7 @@ use std::f64::consts::PI;
 
 use geo::{BooleanOps, BoundingRect, Contains};
 use itertools::Itertools;
+use rand::rngs::StdRng;
 use rand::Rng;
 use rand_distr::{Distribution, Normal};
 
@@ -14,12 +15,10 @@ use vsvg_sketch::Sketch;
 pub struct PolyVorDiagAsteroid;
 
 impl AsteroidMaker for PolyVorDiagAsteroid {
-    fn poly_and_sketch(&self, category: u8) -> (Vec<Point>, Sketch) {
-        let mut rng = rand::thread_rng();
-
+    fn poly_and_sketch(&self, category: u8, rng: &mut StdRng) -> (Vec<Point>, Sketch) {
         let mut sketch = Sketch::new();
 
-        let poly = generate_polygon(1.0, 0.9, 0.13, 18, &mut rng);
+        let poly = generate_polygon(1.0, 0.9, 0.13, 18, rng);
 
         fn voronoi_recurse(
             sketch: &mut Sketch,
@@ -60,7 +59,7 @@ impl AsteroidMaker for PolyVorDiagAsteroid {
             .map(|pt| vsvg::Point::new(pt.x(), pt.y()))
             .collect();
 
-        voronoi_recurse(&mut sketch, &poly, category as usize, 1, &mut rng);
+        voronoi_recurse(&mut sketch, &poly, category as usize, 1, rng);
 
         (boundary, sketch)
     }
@@ -0,0 +1,109 @@
+//! Scales the fixed-size playfield to whatever size the window has.
+
+use crate::Resolution;
+use bevy::prelude::*;
+use bevy::render::camera::{ScalingMode, Viewport};
+use bevy::window::{PrimaryWindow, WindowResized};
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayfieldScaling>()
+            .add_systems(Startup, (spawn_camera_system,))
+            .add_systems(Update, (camera_scaling_system,));
+    }
+}
+
+/// How the playfield is scaled when the window's aspect ratio differs from its own.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayfieldScaling {
+    /// Show the whole playfield, with black bars on the sides the window has in excess.
+    Fit,
+    /// Fill the whole window, cropping the playfield's sides that don't fit.
+    Fill,
+    /// Fill the whole window with the whole playfield, distorting it.
+    Stretch,
+}
+
+impl Default for PlayfieldScaling {
+    /// The scaling is set by the `RUSTEROIDS_SCALING` environment variable (`fit`, `fill` or
+    /// `stretch`), and defaults to fit.
+    fn default() -> Self {
+        match std::env::var("RUSTEROIDS_SCALING").as_deref() {
+            Ok("fill") => Self::Fill,
+            Ok("stretch") => Self::Stretch,
+            _ => Self::Fit,
+        }
+    }
+}
+
+fn spawn_camera_system(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
+
+/// Maps the playfield to the window whenever either of them changes size.
+fn camera_scaling_system(
+    scaling: Res<PlayfieldScaling>,
+    resolution: Res<Resolution>,
+    mut resize_reader: EventReader<WindowResized>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut q_camera: Query<(&mut Camera, &mut OrthographicProjection)>,
+) {
+    let resized = resize_reader.iter().count() > 0;
+    let changed = resized || scaling.is_changed() || resolution.is_changed();
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+
+    let window_size = UVec2::new(window.physical_width(), window.physical_height());
+    if window_size.x == 0 || window_size.y == 0 {
+        // minimised
+        return;
+    }
+
+    let (viewport, scaling_mode) = match *scaling {
+        PlayfieldScaling::Fit => {
+            let scale = (window_size.x as f32 / resolution.width)
+                .min(window_size.y as f32 / resolution.height);
+            let size = (Vec2::new(resolution.width, resolution.height) * scale)
+                .round()
+                .as_uvec2()
+                .clamp(UVec2::ONE, window_size);
+
+            (
+                Some(Viewport {
+                    physical_position: (window_size - size) / 2,
+                    physical_size: size,
+                    ..default()
+                }),
+                ScalingMode::Fixed {
+                    width: resolution.width,
+                    height: resolution.height,
+                },
+            )
+        }
+        PlayfieldScaling::Fill => (
+            None,
+            ScalingMode::AutoMax {
+                max_width: resolution.width,
+                max_height: resolution.height,
+            },
+        ),
+        PlayfieldScaling::Stretch => (
+            None,
+            ScalingMode::Fixed {
+                width: resolution.width,
+                height: resolution.height,
+            },
+        ),
+    };
+
+    for (mut camera, mut projection) in q_camera.iter_mut() {
+        if !(changed || projection.is_added()) {
+            continue;
+        }
+        camera.viewport = viewport.clone();
+        projection.scaling_mode = scaling_mode.clone();
+    }
+}
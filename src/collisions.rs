@@ -0,0 +1,1002 @@
+use crate::components::{
+    Asteroid, Missile, PowerUpPickup, PreviousTransform, RotationSpeed, Saucer, Ship, Speed,
+};
+use crate::game_state::GameState;
+use crate::line_sprite::Shape;
+use crate::resources::{AsteroidCollisions, GameMode};
+use crate::systems::{basic_speed_system, ship_motion_system, wrap_positions};
+use crate::Resolution;
+use bevy::prelude::*;
+use geo::line_intersection::{line_intersection, LineIntersection};
+use geo::{Area, BoundingRect, Contains, Intersects, LinesIter, Translate};
+use itertools::Itertools;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AsteroidCollisions>()
+            .init_resource::<Broadphase>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    record_previous_transform_system.before(basic_speed_system),
+                    update_broadphase_system
+                        .after(basic_speed_system)
+                        .after(ship_motion_system)
+                        .after(wrap_positions),
+                    asteroid_collision_system.after(update_broadphase_system),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Target size of the broadphase cells, adjusted so that they tile the playfield exactly.
+const CELL_SIZE: f32 = 64.0;
+
+/// Geometry of a colliding entity in world coordinates, as of the last broadphase update.
+#[derive(Clone)]
+pub struct Collider {
+    pub entity: Entity,
+    pub center: Vec2,
+    /// For entities with a [`PreviousTransform`], the path swept during the last fixed step.
+    pub geometry: geo::Geometry<f32>,
+    pub bbox: geo::Rect<f32>,
+}
+
+impl Collider {
+    /// Earliest point along this collider's path where it touches another one, and the time
+    /// within the step at which it got there, from 0 to 1.
+    ///
+    /// Only the lines swept by fast entities have a path, which is followed from its start. Other
+    /// colliders touch where they stand, at their center, right at the start. The time, unlike
+    /// the distance, compares the hits of entities moving at different speeds.
+    pub fn earliest_hit(&self, other: &Collider) -> Option<(Vec2, f32)> {
+        let geo::Geometry::LineString(path) = &self.geometry else {
+            return self
+                .geometry
+                .intersects(&other.geometry)
+                .then_some((self.center, 0.0));
+        };
+
+        // the path joins the line where it was to the line where it is, see `swept_geometry`, so
+        // the front of the line moved from the end of the first half to the end of the second one
+        let front = |idx: usize| Vec2::new(path.0[idx].x, path.0[idx].y);
+        let from = front(path.0.len() / 2 - 1);
+        let step = front(path.0.len() - 1) - from;
+        let time = |point: Vec2| {
+            if step.length_squared() > 0.0 {
+                ((point - from).dot(step) / step.length_squared()).clamp(0.0, 1.0)
+            } else {
+                0.0
+            }
+        };
+
+        let edges = match &other.geometry {
+            geo::Geometry::Polygon(polygon) => polygon.lines_iter().collect(),
+            geo::Geometry::LineString(line_string) => line_string.lines_iter().collect(),
+            _ => vec![],
+        };
+
+        for line in path.lines() {
+            let start = Vec2::new(line.start.x, line.start.y);
+            if other.geometry.intersects(&line.start_point()) {
+                return Some((start, time(start)));
+            }
+
+            let hit = edges
+                .iter()
+                .filter_map(|edge| line_intersection(line, *edge))
+                .flat_map(|intersection| match intersection {
+                    LineIntersection::SinglePoint { intersection, .. } => vec![intersection],
+                    LineIntersection::Collinear { intersection } => {
+                        vec![intersection.start, intersection.end]
+                    }
+                })
+                .map(|coord| Vec2::new(coord.x, coord.y))
+                .min_by(|a, b| a.distance(start).total_cmp(&b.distance(start)));
+            if let Some(hit) = hit {
+                return Some((hit, time(hit)));
+            }
+        }
+
+        None
+    }
+}
+
+/// Uniform grid indexing the colliders by the cells their bounding box overlaps, so that only
+/// nearby ones are tested against each other.
+///
+/// The grid tiles the playfield and wraps around its edges, like the entities do, and colliders are
+/// tested against the copies of each other that are nearest across the edges. It is rebuilt every
+/// fixed step by [`update_broadphase_system`], and kept up to date with the asteroids pushed apart
+/// by [`asteroid_collision_system`], which systems looking for hits must run after.
+#[derive(Resource, Default)]
+pub struct Broadphase {
+    colliders: Vec<Collider>,
+    index: HashMap<Entity, usize>,
+    /// Indices of the colliders overlapping each cell, row by row.
+    cells: Vec<Vec<usize>>,
+    columns: usize,
+    rows: usize,
+    cell_size: Vec2,
+    /// Bottom-left corner of the playfield.
+    origin: Vec2,
+    playfield: Vec2,
+}
+
+impl Broadphase {
+    fn rebuild(&mut self, resolution: &Resolution, colliders: impl Iterator<Item = Collider>) {
+        self.columns = (resolution.width / CELL_SIZE).floor().max(1.0) as usize;
+        self.rows = (resolution.height / CELL_SIZE).floor().max(1.0) as usize;
+        self.cell_size = Vec2::new(
+            resolution.width / self.columns as f32,
+            resolution.height / self.rows as f32,
+        );
+        self.playfield = Vec2::new(resolution.width, resolution.height);
+        self.origin = -self.playfield / 2.0;
+
+        self.cells.clear();
+        self.cells.resize_with(self.columns * self.rows, Vec::new);
+        self.colliders.clear();
+        self.index.clear();
+
+        for collider in colliders {
+            let idx = self.colliders.len();
+            for cell in self.cells_overlapping(&collider.bbox) {
+                self.cells[cell].push(idx);
+            }
+            self.index.insert(collider.entity, idx);
+            self.colliders.push(collider);
+        }
+    }
+
+    /// Cells overlapping a bounding box, wrapping around the edges of the playfield.
+    fn cells_overlapping(&self, bbox: &geo::Rect<f32>) -> Vec<usize> {
+        if self.cells.is_empty() {
+            return vec![];
+        }
+
+        let to_cell = |coord: geo::Coord<f32>| {
+            ((Vec2::new(coord.x, coord.y) - self.origin) / self.cell_size).floor()
+        };
+        let min = to_cell(bbox.min());
+        let max = to_cell(bbox.max());
+
+        // a box larger than the playfield overlaps each cell only once
+        let columns = ((max.x - min.x) as usize + 1).min(self.columns);
+        let rows = ((max.y - min.y) as usize + 1).min(self.rows);
+
+        (0..rows)
+            .cartesian_product(0..columns)
+            .map(|(row, column)| {
+                let x = (min.x as i64 + column as i64).rem_euclid(self.columns as i64) as usize;
+                let y = (min.y as i64 + row as i64).rem_euclid(self.rows as i64) as usize;
+                y * self.columns + x
+            })
+            .collect()
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&Collider> {
+        self.index.get(&entity).map(|idx| &self.colliders[*idx])
+    }
+
+    /// Moves an entity's collider along with the entity, between two rebuilds.
+    fn translate(&mut self, entity: Entity, offset: Vec2) {
+        let Some(&idx) = self.index.get(&entity) else {
+            return;
+        };
+
+        for cell in self.cells_overlapping(&self.colliders[idx].bbox) {
+            self.cells[cell].retain(|other| *other != idx);
+        }
+
+        let collider = &mut self.colliders[idx];
+        collider.center += offset;
+        collider.geometry.translate_mut(offset.x, offset.y);
+        collider.bbox.translate_mut(offset.x, offset.y);
+
+        // keep the indices within a cell sorted
+        for cell in self.cells_overlapping(&self.colliders[idx].bbox) {
+            if let Err(pos) = self.cells[cell].binary_search(&idx) {
+                self.cells[cell].insert(pos, idx);
+            }
+        }
+    }
+
+    /// Colliders sharing a cell with a bounding box, in a reproducible order.
+    pub fn candidates(&self, bbox: &geo::Rect<f32>) -> impl Iterator<Item = &Collider> {
+        let mut indices = self
+            .cells_overlapping(bbox)
+            .into_iter()
+            .flat_map(|cell| self.cells[cell].iter().copied())
+            .collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices.dedup();
+
+        indices.into_iter().map(|idx| &self.colliders[idx])
+    }
+
+    /// Copy of a collider nearest to another one, which is a ghost across the playfield's edges
+    /// if they are on opposite sides.
+    pub fn nearest_image<'a>(&self, collider: &'a Collider, to: &Collider) -> Cow<'a, Collider> {
+        let offset = if self.playfield.cmpgt(Vec2::ZERO).all() {
+            -((collider.center - to.center) / self.playfield).round() * self.playfield
+        } else {
+            Vec2::ZERO
+        };
+
+        if offset == Vec2::ZERO {
+            Cow::Borrowed(collider)
+        } else {
+            Cow::Owned(Collider {
+                entity: collider.entity,
+                center: collider.center + offset,
+                geometry: collider.geometry.translate(offset.x, offset.y),
+                bbox: collider.bbox.translate(offset.x, offset.y),
+            })
+        }
+    }
+
+    /// Earliest point along an entity's path where it touches another one, next to the latter,
+    /// see [`Collider::earliest_hit`].
+    pub fn earliest_hit(&self, entity: Entity, other: Entity) -> Option<(Vec2, f32)> {
+        let other = self.get(other)?;
+        self.nearest_image(self.get(entity)?, other)
+            .earliest_hit(other)
+    }
+
+    /// Entities whose collider intersects the given entity's one.
+    pub fn hits(&self, entity: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.get(entity).into_iter().flat_map(move |collider| {
+            self.candidates(&collider.bbox)
+                .filter(move |other| {
+                    other.entity != entity
+                        && self
+                            .nearest_image(other, collider)
+                            .geometry
+                            .intersects(&collider.geometry)
+                })
+                .map(|other| other.entity)
+        })
+    }
+
+    /// Pairs of colliders sharing a cell, each listed once, in a reproducible order.
+    pub fn pairs(&self) -> Vec<(Entity, Entity)> {
+        // the indices within a cell are sorted, so each pair comes in the same order
+        let mut pairs = self
+            .cells
+            .iter()
+            .flat_map(|cell| {
+                cell.iter()
+                    .enumerate()
+                    .flat_map(|(n, a)| cell[n + 1..].iter().map(|b| (*a, *b)))
+            })
+            .collect::<Vec<(usize, usize)>>();
+        pairs.sort_unstable();
+        pairs.dedup();
+
+        pairs
+            .into_iter()
+            .map(|(a, b)| (self.colliders[a].entity, self.colliders[b].entity))
+            .collect()
+    }
+}
+
+/// Remembers where fast entities are before they move.
+pub fn record_previous_transform_system(mut query: Query<(&Transform, &mut PreviousTransform)>) {
+    for (transform, mut previous) in query.iter_mut() {
+        previous.0 = *transform;
+    }
+}
+
+/// Line along which a line shape moved between two transforms.
+///
+/// The previous transform is brought next to the current one if the entity wrapped around the
+/// playfield in between. Other shapes are only tested at their current transform.
+fn swept_geometry(
+    shape: &Shape,
+    previous: &Transform,
+    current: &Transform,
+    resolution: &Resolution,
+) -> Option<geo::Geometry<f32>> {
+    let playfield = Vec2::new(resolution.width, resolution.height);
+    let jump = current.translation.truncate() - previous.translation.truncate();
+    let mut previous = *previous;
+    previous.translation += ((jump / playfield).round() * playfield).extend(0.0);
+
+    let (Some(geo::Geometry::LineString(from)), Some(geo::Geometry::LineString(to))) =
+        (shape.as_geometry(&previous), shape.as_geometry(current))
+    else {
+        return shape.as_geometry(current);
+    };
+
+    Some(geo::LineString::new(from.0.into_iter().chain(to.0).collect()).into())
+}
+
+/// Caches the world-space geometry of everything that can collide, and indexes it.
+#[allow(clippy::type_complexity)]
+pub fn update_broadphase_system(
+    resolution: Res<Resolution>,
+    mut broadphase: ResMut<Broadphase>,
+    query: Query<
+        (Entity, &Transform, &Shape, Option<&PreviousTransform>),
+        Or<(
+            With<Asteroid>,
+            With<Missile>,
+            With<Ship>,
+            With<Saucer>,
+            With<PowerUpPickup>,
+        )>,
+    >,
+) {
+    let colliders = query
+        .iter()
+        .filter_map(|(entity, transform, shape, previous)| {
+            let geometry = match previous {
+                Some(PreviousTransform(previous)) => {
+                    swept_geometry(shape, previous, transform, &resolution)?
+                }
+                None => shape.as_geometry(transform)?,
+            };
+            let bbox = geometry.bounding_rect()?;
+
+            Some(Collider {
+                entity,
+                center: transform.translation.truncate(),
+                geometry,
+                bbox,
+            })
+        });
+
+    broadphase.rebuild(&resolution, colliders);
+}
+
+/// Rigid body properties of an asteroid, in world coordinates.
+struct Body<'a> {
+    polygon: &'a geo::Polygon<f32>,
+    center: Vec2,
+    /// Radius of the bounding circle, for a cheap early rejection.
+    radius: f32,
+    mass: f32,
+    inertia: f32,
+}
+
+impl<'a> Body<'a> {
+    fn new(collider: &'a Collider) -> Option<Self> {
+        let geo::Geometry::Polygon(polygon) = &collider.geometry else {
+            return None;
+        };
+        let center = collider.center;
+
+        let radius = polygon
+            .exterior()
+            .points()
+            .map(|p| Vec2::new(p.x(), p.y()).distance(center))
+            .fold(0.0, f32::max);
+
+        // the mass is the area, only the mass ratios matter for the response
+        let mass = polygon.unsigned_area().max(1.0);
+        // moment of inertia of a disc of the same area
+        let inertia = 0.5 * mass * mass / std::f32::consts::PI;
+
+        Some(Self {
+            polygon,
+            center,
+            radius,
+            mass,
+            inertia,
+        })
+    }
+
+    /// Contact point, normal pointing from `self` to `other`, and how deep the bodies overlap
+    /// along the normal, if they do.
+    fn contact(&self, other: &Body) -> Option<(Vec2, Vec2, f32)> {
+        if self.center.distance(other.center) > self.radius + other.radius
+            || !self.polygon.intersects(other.polygon)
+        {
+            return None;
+        }
+
+        // the vertices of each polygon lying within the other one outline the overlap
+        let inside = self
+            .polygon
+            .exterior()
+            .points()
+            .filter(|p| other.polygon.contains(p))
+            .chain(
+                other
+                    .polygon
+                    .exterior()
+                    .points()
+                    .filter(|p| self.polygon.contains(p)),
+            )
+            .map(|p| Vec2::new(p.x(), p.y()))
+            .collect::<Vec<_>>();
+
+        let point = if inside.is_empty() {
+            (self.center + other.center) / 2.0
+        } else {
+            inside.iter().sum::<Vec2>() / inside.len() as f32
+        };
+        let normal = (other.center - self.center)
+            .try_normalize()
+            .unwrap_or(Vec2::X);
+
+        let along_normal = |p: geo::Point<f32>| Vec2::new(p.x(), p.y()).dot(normal);
+        let depth = self
+            .polygon
+            .exterior()
+            .points()
+            .map(along_normal)
+            .fold(f32::NEG_INFINITY, f32::max)
+            - other
+                .polygon
+                .exterior()
+                .points()
+                .map(along_normal)
+                .fold(f32::INFINITY, f32::min);
+
+        Some((point, normal, depth.max(0.0)))
+    }
+}
+
+/// Bounces overlapping asteroids off each other, and pushes them apart so they don't stick.
+pub fn asteroid_collision_system(
+    mode: Res<GameMode>,
+    settings: Res<AsteroidCollisions>,
+    mut broadphase: ResMut<Broadphase>,
+    mut query: Query<(&mut Transform, &mut Speed, &mut RotationSpeed), With<Asteroid>>,
+) {
+    if !settings.enabled(*mode) {
+        return;
+    }
+
+    let mut pushes = vec![];
+    for (entity_a, entity_b) in broadphase.pairs() {
+        let Ok([asteroid_a, asteroid_b]) = query.get_many_mut([entity_a, entity_b]) else {
+            continue;
+        };
+        let (mut transform_a, mut speed_a, mut rotation_a) = asteroid_a;
+        let (mut transform_b, mut speed_b, mut rotation_b) = asteroid_b;
+
+        let (Some(collider_a), Some(collider_b)) =
+            (broadphase.get(entity_a), broadphase.get(entity_b))
+        else {
+            continue;
+        };
+        // the contact is computed next to `a`, only relative positions matter from then on
+        let collider_b = broadphase.nearest_image(collider_b, collider_a);
+        let (Some(a), Some(b)) = (Body::new(collider_a), Body::new(&collider_b)) else {
+            continue;
+        };
+        let Some((point, normal, depth)) = a.contact(&b) else {
+            continue;
+        };
+
+        let motion_a = Motion {
+            speed: speed_a.0,
+            rotation_speed: rotation_a.0,
+        };
+        let motion_b = Motion {
+            speed: speed_b.0,
+            rotation_speed: rotation_b.0,
+        };
+        if let Some((motion_a, motion_b)) = bounce(
+            &a,
+            &b,
+            point,
+            normal,
+            settings.restitution,
+            motion_a,
+            motion_b,
+        ) {
+            let max = settings.max_rotation_speed;
+            speed_a.0 = motion_a.speed;
+            speed_b.0 = motion_b.speed;
+            rotation_a.0 = motion_a.rotation_speed.clamp(-max, max);
+            rotation_b.0 = motion_b.rotation_speed.clamp(-max, max);
+        }
+
+        let (push_a, push_b) = separation(&a, &b, normal, depth);
+        transform_a.translation += push_a.extend(0.0);
+        transform_b.translation += push_b.extend(0.0);
+        pushes.extend([(entity_a, push_a), (entity_b, push_b)]);
+    }
+
+    // the hits found later in the step must see the asteroids where they now are
+    for (entity, push) in pushes {
+        broadphase.translate(entity, push);
+    }
+}
+
+/// Linear and angular speed of a body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Motion {
+    speed: Vec2,
+    rotation_speed: f32,
+}
+
+/// Motions of two bodies in contact after an impulse along the normal, which points from `a` to
+/// `b`. Only approaching bodies get an impulse, `None` is returned for separating ones.
+fn bounce(
+    a: &Body,
+    b: &Body,
+    point: Vec2,
+    normal: Vec2,
+    restitution: f32,
+    motion_a: Motion,
+    motion_b: Motion,
+) -> Option<(Motion, Motion)> {
+    let arm_a = point - a.center;
+    let arm_b = point - b.center;
+    let velocity_a = motion_a.speed + motion_a.rotation_speed * arm_a.perp();
+    let velocity_b = motion_b.speed + motion_b.rotation_speed * arm_b.perp();
+    let normal_speed = (velocity_b - velocity_a).dot(normal);
+    if normal_speed >= 0.0 {
+        return None;
+    }
+
+    let torque_a = arm_a.perp_dot(normal);
+    let torque_b = arm_b.perp_dot(normal);
+    let impulse = -(1.0 + restitution) * normal_speed
+        / (1.0 / a.mass
+            + 1.0 / b.mass
+            + torque_a * torque_a / a.inertia
+            + torque_b * torque_b / b.inertia);
+
+    Some((
+        Motion {
+            speed: motion_a.speed - normal * impulse / a.mass,
+            rotation_speed: motion_a.rotation_speed - torque_a * impulse / a.inertia,
+        },
+        Motion {
+            speed: motion_b.speed + normal * impulse / b.mass,
+            rotation_speed: motion_b.rotation_speed + torque_b * impulse / b.inertia,
+        },
+    ))
+}
+
+/// Displacements of two overlapping bodies pushing them `distance` further apart along the
+/// normal, which points from `a` to `b`. The lighter body moves the most.
+fn separation(a: &Body, b: &Body, normal: Vec2, distance: f32) -> (Vec2, Vec2) {
+    let push = normal * distance;
+    let total_mass = a.mass + b.mass;
+    (-push * b.mass / total_mass, push * a.mass / total_mass)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TIME_STEP;
+
+    fn square(id: u32, center: Vec2, half_size: f32) -> Collider {
+        let min = center - half_size;
+        let max = center + half_size;
+        let geometry: geo::Geometry<f32> = geo::Rect::new((min.x, min.y), (max.x, max.y))
+            .to_polygon()
+            .into();
+
+        Collider {
+            entity: Entity::from_raw(id),
+            center,
+            bbox: geometry.bounding_rect().unwrap(),
+            geometry,
+        }
+    }
+
+    fn momentum(a: &Body, b: &Body, motion_a: Motion, motion_b: Motion) -> Vec2 {
+        a.mass * motion_a.speed + b.mass * motion_b.speed
+    }
+
+    fn energy(body: &Body, motion: Motion) -> f32 {
+        0.5 * body.mass * motion.speed.length_squared()
+            + 0.5 * body.inertia * motion.rotation_speed * motion.rotation_speed
+    }
+
+    fn broadphase(colliders: impl IntoIterator<Item = Collider>) -> Broadphase {
+        let mut broadphase = Broadphase::default();
+        broadphase.rebuild(&RESOLUTION, colliders.into_iter());
+        broadphase
+    }
+
+    /// Cells overlapped by a square, as (column, row) pairs.
+    fn cells(broadphase: &Broadphase, center: Vec2, half_size: f32) -> Vec<(usize, usize)> {
+        let mut cells = broadphase
+            .cells_overlapping(&square(0, center, half_size).bbox)
+            .into_iter()
+            .map(|cell| (cell % broadphase.columns, cell / broadphase.columns))
+            .collect::<Vec<_>>();
+        cells.sort_unstable();
+        cells
+    }
+
+    fn hits(broadphase: &Broadphase, id: u32) -> Vec<u32> {
+        broadphase
+            .hits(Entity::from_raw(id))
+            .map(|entity| entity.index())
+            .collect()
+    }
+
+    #[test]
+    fn cells_wrap_around_the_edges() {
+        let broadphase = broadphase([]);
+        // 12 columns and 9 rows of 66.7 units
+        assert_eq!((broadphase.columns, broadphase.rows), (12, 9));
+
+        assert_eq!(cells(&broadphase, Vec2::new(30.0, 10.0), 5.0), vec![(6, 4)]);
+        // right and left edges
+        assert_eq!(
+            cells(&broadphase, Vec2::new(398.0, 10.0), 5.0),
+            vec![(0, 4), (11, 4)]
+        );
+        assert_eq!(
+            cells(&broadphase, Vec2::new(-398.0, 10.0), 5.0),
+            vec![(0, 4), (11, 4)]
+        );
+        // top and bottom edges
+        assert_eq!(
+            cells(&broadphase, Vec2::new(30.0, 298.0), 5.0),
+            vec![(6, 0), (6, 8)]
+        );
+        assert_eq!(
+            cells(&broadphase, Vec2::new(30.0, -298.0), 5.0),
+            vec![(6, 0), (6, 8)]
+        );
+        // corner
+        assert_eq!(
+            cells(&broadphase, Vec2::new(398.0, -298.0), 5.0),
+            vec![(0, 0), (0, 8), (11, 0), (11, 8)]
+        );
+        // a box larger than the playfield overlaps each cell once
+        assert_eq!(cells(&broadphase, Vec2::ZERO, 1000.0).len(), 12 * 9);
+    }
+
+    #[test]
+    fn hits_across_the_edges() {
+        let broadphase = broadphase([
+            // overlapping across the right and left edges
+            square(0, Vec2::new(397.0, 0.0), 5.0),
+            square(1, Vec2::new(-397.0, 0.0), 5.0),
+            // overlapping across the top-left and bottom-right corners
+            square(2, Vec2::new(-397.0, 297.0), 5.0),
+            square(3, Vec2::new(397.0, -297.0), 5.0),
+            // in the same cells as the first pair, but not overlapping it
+            square(4, Vec2::new(380.0, 20.0), 5.0),
+        ]);
+
+        assert_eq!(hits(&broadphase, 0), vec![1]);
+        assert_eq!(hits(&broadphase, 1), vec![0]);
+        assert_eq!(hits(&broadphase, 2), vec![3]);
+        assert_eq!(hits(&broadphase, 3), vec![2]);
+        assert!(hits(&broadphase, 4).is_empty());
+    }
+
+    #[test]
+    fn nearest_image_across_the_seam() {
+        let broadphase = broadphase([]);
+        let right = square(0, Vec2::new(395.0, 295.0), 5.0);
+        let left = square(1, Vec2::new(-395.0, 0.0), 5.0);
+        let bottom_left = square(2, Vec2::new(-395.0, -295.0), 5.0);
+
+        let image = broadphase.nearest_image(&right, &left);
+        assert_eq!(image.center, Vec2::new(-405.0, 295.0));
+        assert_eq!(image.bbox.min(), geo::coord! { x: -410.0, y: 290.0 });
+
+        let image = broadphase.nearest_image(&right, &bottom_left);
+        assert_eq!(image.center, Vec2::new(-405.0, -305.0));
+        assert!(image.geometry.intersects(&geo::Point::new(-405.0, -305.0)));
+
+        // already the nearest
+        let image = broadphase.nearest_image(&left, &bottom_left);
+        assert!(matches!(image, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn pairs_are_listed_once_in_order() {
+        // overlapping many cells, and each other
+        let colliders = [
+            square(7, Vec2::new(0.0, 0.0), 100.0),
+            square(3, Vec2::new(50.0, 0.0), 100.0),
+            square(5, Vec2::new(0.0, 50.0), 100.0),
+            square(1, Vec2::new(-398.0, 0.0), 5.0),
+        ];
+
+        let pairs = broadphase(colliders.clone())
+            .pairs()
+            .into_iter()
+            .map(|(a, b)| (a.index(), b.index()))
+            .collect::<Vec<_>>();
+        assert_eq!(pairs, vec![(7, 3), (7, 5), (3, 5)]);
+
+        // the order only depends on the order of the colliders
+        let mut reversed = colliders;
+        reversed.reverse();
+        let pairs = broadphase(reversed)
+            .pairs()
+            .into_iter()
+            .map(|(a, b)| (a.index(), b.index()))
+            .collect::<Vec<_>>();
+        assert_eq!(pairs, vec![(5, 3), (5, 7), (3, 7)]);
+    }
+
+    const RESOLUTION: Resolution = Resolution {
+        width: 800.0,
+        height: 600.0,
+    };
+
+    fn missile(id: u32, previous: Transform, current: Transform) -> Collider {
+        let shape = Shape::from_vertices([Vec2::new(0.0, 0.0), Vec2::new(0.0, 4.0)], false);
+        let geometry = swept_geometry(&shape, &previous, &current, &RESOLUTION).unwrap();
+
+        Collider {
+            entity: Entity::from_raw(id),
+            center: current.translation.truncate(),
+            bbox: geometry.bounding_rect().unwrap(),
+            geometry,
+        }
+    }
+
+    /// Diamond shaped asteroid, as large as the smallest category 1 asteroids.
+    fn asteroid(id: u32, center: Vec2) -> Collider {
+        let shape = Shape::from_vertices([Vec2::X, Vec2::Y, Vec2::NEG_X, Vec2::NEG_Y], true);
+        let transform =
+            Transform::from_translation(center.extend(0.0)).with_scale(Vec3::splat(8.0));
+        let geometry = shape.as_geometry(&transform).unwrap();
+
+        Collider {
+            entity: Entity::from_raw(id),
+            center,
+            bbox: geometry.bounding_rect().unwrap(),
+            geometry,
+        }
+    }
+
+    #[test]
+    fn fast_missiles_dont_tunnel() {
+        // the tip of the asteroid is 2 units thick, a step of the missile is 7.5 units long
+        let step = Missile::default().speed * TIME_STEP;
+        let previous = Transform::from_xyz(7.0, -5.5, 0.0);
+        let current = Transform::from_xyz(7.0, -5.5 + step, 0.0);
+        let asteroid = asteroid(1, Vec2::ZERO);
+
+        let shape = Shape::from_vertices([Vec2::new(0.0, 0.0), Vec2::new(0.0, 4.0)], false);
+        for transform in [previous, current] {
+            let geometry = shape.as_geometry(&transform).unwrap();
+            assert!(!geometry.intersects(&asteroid.geometry));
+        }
+
+        let (point, time) = missile(0, previous, current)
+            .earliest_hit(&asteroid)
+            .unwrap();
+        assert!(point.distance(Vec2::new(7.0, -1.0)) < 1e-4, "{}", point);
+        // the tip started half a unit away from the asteroid
+        assert!((time - 0.5 / step).abs() < 1e-4);
+
+        // unswept colliders are hit right at the start
+        assert_eq!(asteroid.earliest_hit(&asteroid), Some((Vec2::ZERO, 0.0)));
+    }
+
+    #[test]
+    fn sweep_across_the_seam() {
+        // heading right, wrapping around from the right edge to the left one
+        let heading = Quat::from_rotation_z(-std::f32::consts::FRAC_PI_2);
+        let previous = Transform::from_xyz(395.0, 7.0, 0.0).with_rotation(heading);
+        let current = Transform::from_xyz(-397.5, 7.0, 0.0).with_rotation(heading);
+
+        let broadphase = broadphase([
+            missile(0, previous, current),
+            // within the gap between the two positions, across the seam
+            asteroid(1, Vec2::new(-399.25, 0.0)),
+            // on the way if the sweep went back across the whole playfield
+            asteroid(2, Vec2::new(0.0, 0.0)),
+        ]);
+
+        let (point, time) = broadphase
+            .earliest_hit(Entity::from_raw(0), Entity::from_raw(1))
+            .unwrap();
+        assert!(point.distance(Vec2::new(-400.25, 7.0)) < 1e-3, "{}", point);
+        // the tip started at 391, i.e. -409 across the seam, and covered 0.75 of 7.5 units
+        assert!((time - 0.1).abs() < 1e-3);
+
+        assert_eq!(
+            broadphase.earliest_hit(Entity::from_raw(0), Entity::from_raw(2)),
+            None
+        );
+        assert_eq!(hits(&broadphase, 0), vec![1]);
+    }
+
+    #[test]
+    fn head_on_bounce_conserves_momentum() {
+        let collider_a = square(0, Vec2::new(-9.0, 0.0), 10.0);
+        let collider_b = square(1, Vec2::new(5.5, 0.0), 5.0);
+        let (a, b) = (
+            Body::new(&collider_a).unwrap(),
+            Body::new(&collider_b).unwrap(),
+        );
+        let (point, normal, _) = a.contact(&b).unwrap();
+        assert_eq!(normal, Vec2::X);
+
+        let motion_a = Motion {
+            speed: Vec2::new(20.0, 0.0),
+            rotation_speed: 0.0,
+        };
+        let motion_b = Motion {
+            speed: Vec2::new(-30.0, 0.0),
+            rotation_speed: 0.0,
+        };
+        let (after_a, after_b) = bounce(&a, &b, point, normal, 1.0, motion_a, motion_b).unwrap();
+
+        let before = momentum(&a, &b, motion_a, motion_b);
+        let after = momentum(&a, &b, after_a, after_b);
+        assert!(before.distance(after) < 1e-2, "{} != {}", before, after);
+
+        // elastic, the relative speed is reversed, and the hit is central so nothing spins
+        let relative = after_b.speed - after_a.speed;
+        assert!((relative - Vec2::new(50.0, 0.0)).length() < 1e-3);
+        assert_eq!(after_a.rotation_speed, 0.0);
+        assert_eq!(after_b.rotation_speed, 0.0);
+    }
+
+    #[test]
+    fn off_center_bounce_conserves_momentum_and_energy() {
+        let collider_a = square(0, Vec2::new(-9.0, 0.0), 10.0);
+        let collider_b = square(1, Vec2::new(5.5, 8.0), 5.0);
+        let (a, b) = (
+            Body::new(&collider_a).unwrap(),
+            Body::new(&collider_b).unwrap(),
+        );
+        let (point, normal, _) = a.contact(&b).unwrap();
+
+        let motion_a = Motion {
+            speed: Vec2::new(20.0, 5.0),
+            rotation_speed: 0.5,
+        };
+        let motion_b = Motion {
+            speed: Vec2::new(-30.0, -10.0),
+            rotation_speed: -1.0,
+        };
+        let (after_a, after_b) = bounce(&a, &b, point, normal, 1.0, motion_a, motion_b).unwrap();
+
+        let before = momentum(&a, &b, motion_a, motion_b);
+        let after = momentum(&a, &b, after_a, after_b);
+        assert!(before.distance(after) < 1e-1, "{} != {}", before, after);
+
+        let before = energy(&a, motion_a) + energy(&b, motion_b);
+        let after = energy(&a, after_a) + energy(&b, after_b);
+        assert!(
+            (before - after).abs() / before < 1e-4,
+            "{} != {}",
+            before,
+            after
+        );
+        assert_ne!(after_b.rotation_speed, motion_b.rotation_speed);
+    }
+
+    #[test]
+    fn separating_bodies_dont_bounce() {
+        let collider_a = square(0, Vec2::new(-9.0, 0.0), 10.0);
+        let collider_b = square(1, Vec2::new(5.5, 0.0), 5.0);
+        let (a, b) = (
+            Body::new(&collider_a).unwrap(),
+            Body::new(&collider_b).unwrap(),
+        );
+        let (point, normal, _) = a.contact(&b).unwrap();
+
+        let apart = Motion {
+            speed: Vec2::new(10.0, 0.0),
+            rotation_speed: 0.0,
+        };
+        let still = Motion {
+            speed: Vec2::ZERO,
+            rotation_speed: 0.0,
+        };
+        assert_eq!(bounce(&a, &b, point, normal, 1.0, still, apart), None);
+    }
+
+    #[test]
+    fn overlapping_bodies_are_pushed_apart() {
+        let collider_a = square(0, Vec2::new(-9.0, 0.0), 10.0);
+        let collider_b = square(1, Vec2::new(5.5, 0.0), 5.0);
+        let (a, b) = (
+            Body::new(&collider_a).unwrap(),
+            Body::new(&collider_b).unwrap(),
+        );
+        let (_, normal, depth) = a.contact(&b).unwrap();
+        assert!((depth - 0.5).abs() < 1e-5);
+
+        let (push_a, push_b) = separation(&a, &b, normal, 2.0);
+
+        assert!(push_a.x < 0.0 && push_b.x > 0.0);
+        assert!(((push_b - push_a).length() - 2.0).abs() < 1e-5);
+        // the lighter asteroid moves the most, and the center of mass stays put
+        assert!(push_b.length() > push_a.length());
+        assert!((a.mass * push_a + b.mass * push_b).length() < 1e-3);
+    }
+
+    #[test]
+    fn overlap_is_undone_in_one_step() {
+        let mut app = App::new();
+        let mut settings = AsteroidCollisions::default();
+        settings.modes.insert(GameMode::Single);
+        app.insert_resource(RESOLUTION)
+            .insert_resource(GameMode::Single)
+            .insert_resource(settings)
+            .init_resource::<Broadphase>()
+            .add_systems(
+                Update,
+                (
+                    update_broadphase_system,
+                    asteroid_collision_system.after(update_broadphase_system),
+                ),
+            );
+
+        // deeply overlapping, and moving apart already so they don't bounce
+        let square = Shape::from_vertices(
+            [
+                Vec2::new(1.0, 1.0),
+                Vec2::new(-1.0, 1.0),
+                Vec2::new(-1.0, -1.0),
+                Vec2::new(1.0, -1.0),
+            ],
+            true,
+        );
+        let mut spawn = |x: f32, half_size: f32, speed: f32| {
+            app.world
+                .spawn((
+                    Asteroid { category: 3 },
+                    Transform::from_xyz(x, 0.0, 0.0).with_scale(Vec3::splat(half_size)),
+                    Speed(Vec2::new(speed, 0.0)),
+                    RotationSpeed(0.0),
+                    square.clone(),
+                ))
+                .id()
+        };
+        let a = spawn(-9.0, 10.0, -1.0);
+        let b = spawn(2.0, 5.0, 1.0);
+
+        app.update();
+
+        let broadphase = app.world.resource::<Broadphase>();
+        let (collider_a, collider_b) = (broadphase.get(a).unwrap(), broadphase.get(b).unwrap());
+        // the cached colliders moved along with the asteroids
+        for (entity, collider) in [(a, collider_a), (b, collider_b)] {
+            let transform = app.world.get::<Transform>(entity).unwrap();
+            assert_eq!(collider.center, transform.translation.truncate());
+        }
+        assert!((collider_b.center.x - collider_a.center.x - 15.0).abs() < 1e-4);
+
+        // no more than touching
+        let (body_a, body_b) = (
+            Body::new(collider_a).unwrap(),
+            Body::new(collider_b).unwrap(),
+        );
+        if let Some((_, _, depth)) = body_a.contact(&body_b) {
+            assert!(depth < 1e-4);
+        }
+    }
+
+    #[test]
+    fn translated_colliders_are_reindexed() {
+        let mut broadphase = broadphase([
+            square(0, Vec2::new(30.0, 10.0), 5.0),
+            square(1, Vec2::new(130.0, 10.0), 5.0),
+        ]);
+        assert!(hits(&broadphase, 0).is_empty());
+
+        broadphase.translate(Entity::from_raw(0), Vec2::new(95.0, 0.0));
+
+        assert_eq!(broadphase.get(Entity::from_raw(0)).unwrap().center.x, 125.0);
+        assert_eq!(hits(&broadphase, 0), vec![1]);
+        assert_eq!(
+            broadphase.pairs(),
+            vec![(Entity::from_raw(0), Entity::from_raw(1))]
+        );
+        // gone from the cells it left
+        let left = broadphase.cells_overlapping(&square(2, Vec2::new(30.0, 10.0), 5.0).bbox);
+        assert!(left.iter().all(|cell| broadphase.cells[*cell].is_empty()));
+    }
+}
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use std::collections::BTreeMap;
 
 // ============================================
 // Common
@@ -12,19 +13,39 @@ pub struct RotationSpeed(pub f32);
 #[derive(Component, Debug, Default)]
 pub struct LifeTime(pub Timer);
 
+/// Transform at the start of the current fixed step, so that fast entities are tested for
+/// collisions along the whole path they covered rather than only where they end up.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct PreviousTransform(pub Transform);
+
 // ============================================
 // Ship
 
+/// Identifies the player controlling a ship, starting from 0.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Player(pub usize);
+
+impl Player {
+    /// Number of players sharing the keyboard and gamepads.
+    pub const MAX: usize = 2;
+}
+
 #[derive(Component)]
 pub struct Ship {
     pub thrust_accel: f32,
     pub idle_accel: f32,
     pub max_speed: f32,
     pub rot_speed: f32,
+    /// Time spent invisible and intangible during a hyperspace jump, in seconds.
+    pub hyperspace_duration: f32,
+    /// Probability of the ship blowing up when coming out of hyperspace.
+    pub hyperspace_failure_chance: f64,
 }
 
 impl Ship {
     pub const MISSILE_SPAWN_OFFSET: f32 = 10.0;
+    /// Awarded for shooting down another player's ship in versus mode.
+    pub const POINTS: u32 = 500;
 }
 impl Default for Ship {
     fn default() -> Self {
@@ -33,6 +54,8 @@ impl Default for Ship {
             idle_accel: -10.0,
             max_speed: 450.0,
             rot_speed: 5.0,
+            hyperspace_duration: 0.5,
+            hyperspace_failure_chance: 0.1,
         }
     }
 }
@@ -42,17 +65,72 @@ pub struct Thruster {
     pub active: bool,
 }
 
+/// Missile launcher of a ship.
+#[derive(Component, Debug)]
+pub struct Weapon {
+    /// Minimum time between two shots, in seconds.
+    pub cooldown: f32,
+    /// Maximum number of the ship's missiles on screen at once.
+    pub max_missiles: usize,
+    /// Keeps firing while the fire key is held down.
+    pub autofire: bool,
+    /// Time left before the next shot is allowed.
+    pub reload: Timer,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Self::new(0.15, 4, false)
+    }
+}
+
+impl Weapon {
+    pub fn new(cooldown: f32, max_missiles: usize, autofire: bool) -> Self {
+        let mut reload = Timer::from_seconds(cooldown, TimerMode::Once);
+        // ready to fire right away
+        reload.tick(reload.duration());
+
+        Self {
+            cooldown,
+            max_missiles,
+            autofire,
+            reload,
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct Flame;
 
+/// Outline drawn around the ship while its shield is up.
+#[derive(Component)]
+pub struct ShieldRing;
+
+/// Ship in the middle of a hyperspace jump, hidden and out of reach until the timer finishes.
+#[derive(Component, Debug)]
+pub struct Hyperspace(pub Timer);
+
+/// Line art marking the departure and arrival points of a hyperspace jump.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HyperspaceEffect {
+    /// Bursts outward from the point the ship left.
+    Departure,
+    /// Converges on the point where the ship will reappear.
+    Arrival,
+}
+
 // ============================================
 // Missile
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Missile {
     pub time_to_live: f32,
     pub speed: f32,
     pub momentum_transfer: f32,
+    /// Piercing missiles keep flying after destroying an asteroid.
+    pub piercing: bool,
+    /// Player who fired the missile, if any.
+    pub owner: Option<Player>,
 }
 
 impl Default for Missile {
@@ -61,10 +139,20 @@ impl Default for Missile {
             time_to_live: 1.0,
             speed: 450.0,
             momentum_transfer: 0.2,
+            piercing: false,
+            owner: None,
         }
     }
 }
 
+/// Keeps a piercing missile from hitting again right after going through an asteroid.
+#[derive(Component, Debug)]
+pub struct PierceCooldown(pub Timer);
+
+/// Marks missiles fired by enemies, which hurt the ship, and break asteroids without scoring.
+#[derive(Component, Debug, Default)]
+pub struct Hostile;
+
 // ============================================
 // Asteroid
 
@@ -73,3 +161,105 @@ pub struct Asteroid {
     /// "size" of the asteroid, breaks into asteroids of category `category - 1`
     pub category: u8,
 }
+
+impl Asteroid {
+    /// Points awarded for destroying an asteroid of the given category.
+    pub fn points(category: u8) -> u32 {
+        match category {
+            0 | 1 => 100,
+            2 => 50,
+            _ => 20,
+        }
+    }
+}
+
+// ============================================
+// Saucer
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaucerSize {
+    Large,
+    Small,
+}
+
+#[derive(Component, Debug)]
+pub struct Saucer {
+    pub size: SaucerSize,
+    /// Horizontal cruise speed.
+    pub speed: f32,
+    pub fire_timer: Timer,
+    /// Time between changes of vertical direction.
+    pub course_timer: Timer,
+}
+
+impl Saucer {
+    pub fn new(size: SaucerSize) -> Self {
+        let (speed, fire_period, course_period) = match size {
+            SaucerSize::Large => (80.0, 1.2, 1.5),
+            SaucerSize::Small => (120.0, 0.9, 1.0),
+        };
+
+        Self {
+            size,
+            speed,
+            fire_timer: Timer::from_seconds(fire_period, TimerMode::Repeating),
+            course_timer: Timer::from_seconds(course_period, TimerMode::Repeating),
+        }
+    }
+
+    /// Points awarded for destroying a saucer of the given size.
+    pub fn points(size: SaucerSize) -> u32 {
+        match size {
+            SaucerSize::Large => 200,
+            SaucerSize::Small => 1000,
+        }
+    }
+
+    pub fn scale(size: SaucerSize) -> f32 {
+        match size {
+            SaucerSize::Large => 1.0,
+            SaucerSize::Small => 0.5,
+        }
+    }
+}
+
+// ============================================
+// Power-ups
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PowerUpKind {
+    Shield,
+    SpreadShot,
+    RapidFire,
+    Piercing,
+    LongRange,
+}
+
+/// Power-up waiting to be collected by the ship.
+#[derive(Component, Debug)]
+pub struct PowerUpPickup(pub PowerUpKind);
+
+/// Power-up effects currently active on the ship, with their remaining time.
+#[derive(Component, Debug, Default)]
+pub struct PowerUps {
+    pub active: BTreeMap<PowerUpKind, Timer>,
+}
+
+impl PowerUps {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.active.contains_key(&kind)
+    }
+
+    /// Activates an effect, or restarts its timer if it is already active.
+    pub fn activate(&mut self, kind: PowerUpKind, duration: f32) {
+        self.active
+            .insert(kind, Timer::from_seconds(duration, TimerMode::Once));
+    }
+}
+
+// ============================================
+// Debris
+
+/// Fragment of a destroyed object, which shrinks away over its [`LifeTime`].
+#[derive(Component, Debug, Default)]
+pub struct Debris;
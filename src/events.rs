@@ -1,8 +1,27 @@
+use crate::components::{Player, SaucerSize};
+use crate::line_sprite::Shape;
 use bevy::prelude::*;
 
+/// Sent when an asteroid is destroyed.
+///
+/// The asteroid entity is despawned by the time this event is read, so it carries everything
+/// needed to react to the destruction.
 #[derive(Event)]
 pub struct AsteroidKillEvent {
     pub id: Entity,
+    pub category: u8,
+    pub transform: Transform,
+    pub speed: Vec2,
+    pub rotation_speed: f32,
+    pub shape: Shape,
+    /// Outline and decoration segments of the asteroid, in local coordinates.
+    pub segments: Vec<(Vec2, Vec2)>,
+    /// Point of impact, in world coordinates.
+    pub impact_point: Vec2,
+    /// Entity responsible for the destruction (e.g. the missile).
+    pub killer: Entity,
+    /// Player credited with the destruction.
+    pub by: Option<Player>,
 }
 
 #[derive(Event)]
@@ -10,4 +29,47 @@ pub struct AsteroidSpawnEvent {
     pub category: u8,
     pub start_position: Option<Vec2>,
     pub start_speed: Option<Vec2>,
+    pub start_rotation_speed: Option<f32>,
+    /// Geometry of the asteroid, randomly generated if not provided.
+    pub geometry: Option<AsteroidGeometry>,
+}
+
+/// Geometry of an asteroid cut out of a larger one.
+pub struct AsteroidGeometry {
+    pub shape: Shape,
+    /// Decoration segments, the outline being drawn from the shape.
+    pub segments: Vec<(Vec2, Vec2)>,
+    /// Rotation and scale of the larger asteroid, in which the shape and segments are expressed.
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+#[derive(Event)]
+pub struct ShipKillEvent {
+    pub player: Player,
+    pub position: Vec2,
+    /// Other player credited with the destruction, in versus mode.
+    pub by: Option<Player>,
+}
+
+#[derive(Event)]
+pub struct WaveStartEvent {
+    pub wave: u32,
+}
+
+#[derive(Event)]
+pub struct WaveClearedEvent {
+    pub wave: u32,
+}
+
+/// Sent when a saucer is destroyed.
+#[derive(Event)]
+pub struct SaucerKillEvent {
+    pub size: SaucerSize,
+    pub transform: Transform,
+    pub speed: Vec2,
+    /// Segments of the saucer, in local coordinates.
+    pub segments: Vec<(Vec2, Vec2)>,
+    /// Player who destroyed the saucer, and should be awarded its points.
+    pub by: Option<Player>,
 }
@@ -0,0 +1,310 @@
+use crate::actions::{Action, ActionState};
+use crate::components::{Asteroid, Debris, HyperspaceEffect, Missile, PowerUpPickup, Saucer, Ship};
+use crate::events::AsteroidSpawnEvent;
+use crate::line_sprite::{LineMaterial, LineSpriteBundleBuilder, Shape};
+use crate::resources::{
+    ActionBuffer, GameMode, GameRng, Lives, SaucerSpawner, Score, ShipRespawn, Wave, WaveDirector,
+};
+use crate::stroke_font::{HAlign, StrokeText, VAlign};
+use crate::svg_export::{NoSvgExport, SvgText};
+use crate::systems::spawn_ship;
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use vsvg::Transforms;
+
+#[derive(States, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum GameState {
+    #[default]
+    Title,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+/// Clears the playfield and starts a new game, when leaving the title or game over screens.
+///
+/// Being a schedule of its own, it can also be run to restart a game without a state change.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NewGame;
+
+pub struct GameStatePlugin;
+
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<GameState>()
+            .init_resource::<GameMode>()
+            .add_systems(
+                OnEnter(GameState::Title),
+                (spawn_title_text, spawn_mode_text),
+            )
+            .add_systems(OnEnter(GameState::Paused), (spawn_paused_text,))
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                (spawn_game_over_text, spawn_mode_text),
+            )
+            .add_systems(OnExit(GameState::Title), (despawn_state_text, run_new_game))
+            .add_systems(OnExit(GameState::Paused), (despawn_state_text,))
+            .add_systems(
+                OnExit(GameState::GameOver),
+                (despawn_state_text, run_new_game),
+            )
+            .add_systems(NewGame, (new_game,))
+            .add_systems(
+                Update,
+                (
+                    start_game_system
+                        .run_if(in_state(GameState::Title).or_else(in_state(GameState::GameOver))),
+                    switch_mode_system
+                        .run_if(in_state(GameState::Title).or_else(in_state(GameState::GameOver))),
+                    pause_system
+                        .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                (game_over_system.run_if(in_state(GameState::Playing)),),
+            );
+    }
+}
+
+/// Marks the text displayed for the duration of a non-playing state.
+#[derive(Component)]
+struct StateText;
+
+/// Marks the part of the state text showing the game mode.
+#[derive(Component)]
+struct ModeText;
+
+const TITLE_SIZE: f32 = 48.0;
+const SUBTITLE_SIZE: f32 = 16.0;
+const MODE_TEXT_OFFSET: f32 = 80.0;
+
+fn spawn_state_text(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<LineMaterial>>,
+    title: &str,
+    subtitle: &str,
+) -> Entity {
+    let title = StrokeText::new(title)
+        .size(TITLE_SIZE)
+        .align(HAlign::Center, VAlign::Bottom);
+    let subtitle = StrokeText::new(subtitle)
+        .size(SUBTITLE_SIZE)
+        .align(HAlign::Center, VAlign::Top);
+
+    let subtitle_offset = Vec2::new(0.0, -SUBTITLE_SIZE);
+    let paths = title
+        .paths()
+        .into_iter()
+        .chain(subtitle.paths().into_iter().map(|mut path| {
+            path.translate(subtitle_offset.x as f64, subtitle_offset.y as f64);
+            path
+        }))
+        .collect();
+
+    commands
+        .spawn((
+            StateText,
+            SvgText(paths),
+            LineSpriteBundleBuilder::new(Shape::LineString(vec![]))
+                .add_segments(title.segments())
+                .add_segments(
+                    subtitle
+                        .segments()
+                        .into_iter()
+                        .map(|(a, b)| (a + subtitle_offset, b + subtitle_offset)),
+                )
+                .build(meshes, materials),
+        ))
+        .id()
+}
+
+fn spawn_title_text(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+) {
+    spawn_state_text(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        "RUSTEROIDS",
+        "PRESS SPACE TO START",
+    );
+}
+
+fn spawn_paused_text(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+) {
+    // the pause overlay must not end up in screenshots of the frozen game
+    let entity = spawn_state_text(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        "PAUSED",
+        "PRESS P TO RESUME",
+    );
+    commands.entity(entity).insert(NoSvgExport);
+}
+
+fn spawn_game_over_text(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+) {
+    spawn_state_text(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        "GAME OVER",
+        "PRESS SPACE TO PLAY AGAIN",
+    );
+}
+
+/// Shows the selected game mode below the state text.
+fn spawn_mode_text_sprite(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<LineMaterial>>,
+    mode: GameMode,
+) {
+    let text = StrokeText::new(format!("{}\nPRESS M TO CHANGE", mode.label()))
+        .size(SUBTITLE_SIZE)
+        .align(HAlign::Center, VAlign::Top);
+
+    commands.spawn((
+        StateText,
+        ModeText,
+        SvgText(text.paths()),
+        LineSpriteBundleBuilder::new(Shape::LineString(vec![]))
+            .add_segments(text.segments())
+            .transform(Transform::from_xyz(0.0, -MODE_TEXT_OFFSET, 0.0))
+            .build(meshes, materials),
+    ));
+}
+
+fn spawn_mode_text(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    mode: Res<GameMode>,
+) {
+    spawn_mode_text_sprite(&mut commands, &mut meshes, &mut materials, *mode);
+}
+
+fn switch_mode_system(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    mut mode: ResMut<GameMode>,
+    query: Query<Entity, With<ModeText>>,
+) {
+    if !actions.just_pressed(Action::SwitchMode) {
+        return;
+    }
+
+    *mode = mode.next();
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_mode_text_sprite(&mut commands, &mut meshes, &mut materials, *mode);
+}
+
+fn despawn_state_text(mut commands: Commands, query: Query<Entity, With<StateText>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn run_new_game(world: &mut World) {
+    world.run_schedule(NewGame);
+}
+
+/// Clears the playfield and resets the game resources before a new game starts.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn new_game(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    mut score: ResMut<Score>,
+    mut wave: ResMut<Wave>,
+    mut director: ResMut<WaveDirector>,
+    mut lives: ResMut<Lives>,
+    mut respawn: ResMut<ShipRespawn>,
+    mut saucer_spawner: ResMut<SaucerSpawner>,
+    mut action_buffer: ResMut<ActionBuffer>,
+    mut game_rng: ResMut<GameRng>,
+    mut spawn_events: ResMut<Events<AsteroidSpawnEvent>>,
+    query: Query<
+        Entity,
+        Or<(
+            With<Ship>,
+            With<Asteroid>,
+            With<Missile>,
+            With<Debris>,
+            With<Saucer>,
+            With<HyperspaceEffect>,
+            With<PowerUpPickup>,
+        )>,
+    >,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_events.clear();
+
+    *score = Score::new(*mode);
+    *wave = Wave::default();
+    *director = WaveDirector::default();
+    *lives = Lives::new(*mode);
+    *respawn = ShipRespawn::new(*mode);
+    *saucer_spawner = SaucerSpawner::default();
+    *action_buffer = ActionBuffer::default();
+    game_rng.reset();
+    info!("new game, seed {}", game_rng.seed);
+
+    for player in mode.players() {
+        spawn_ship(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            player,
+            respawn.spawn_point(player),
+        );
+    }
+}
+
+fn start_game_system(actions: Res<ActionState>, mut next_state: ResMut<NextState<GameState>>) {
+    if actions.just_pressed(Action::Fire) {
+        next_state.set(GameState::Playing);
+    }
+}
+
+fn pause_system(
+    actions: Res<ActionState>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        next_state.set(match state.get() {
+            GameState::Paused => GameState::Playing,
+            _ => GameState::Paused,
+        });
+    }
+}
+
+fn game_over_system(
+    lives: Res<Lives>,
+    q_ship: Query<(), With<Ship>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if lives.0.iter().all(|lives| *lives == 0) && q_ship.is_empty() {
+        next_state.set(GameState::GameOver);
+    }
+}
@@ -0,0 +1,227 @@
+//! Draws the parts of line sprites hanging over the playfield's edges on the opposite side, so
+//! that the playfield wraps around seamlessly.
+
+use crate::line_sprite::{LineMaterial, Segments};
+use crate::Resolution;
+use bevy::prelude::*;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+use bevy::transform::TransformSystem;
+use itertools::Itertools;
+use std::collections::HashSet;
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (ghost_system.before(TransformSystem::TransformPropagate),),
+        );
+    }
+}
+
+/// Copy of a line sprite drawn across the playfield's edges.
+#[derive(Component, Debug)]
+struct Ghost {
+    source: Entity,
+    /// Position relative to the source, in multiples of the playfield's size.
+    offset: IVec2,
+}
+
+/// Copy of a child sprite of a ghost's source, e.g. the ship's flame, as a child of the ghost.
+#[derive(Component, Debug)]
+struct GhostChild {
+    source: Entity,
+}
+
+/// Offsets, in multiples of the playfield's size, of the copies needed to show the parts of a
+/// bounding box hanging over the playfield's edges.
+pub fn wrap_offsets(min: Vec2, max: Vec2, playfield: Vec2) -> Vec<IVec2> {
+    let half = playfield / 2.0;
+    let offsets = |min: f32, max: f32, half: f32| {
+        [0].into_iter()
+            .chain((min < -half).then_some(1))
+            .chain((max > half).then_some(-1))
+    };
+
+    offsets(min.x, max.x, half.x)
+        .cartesian_product(offsets(min.y, max.y, half.y).collect::<Vec<_>>())
+        .map(|(x, y)| IVec2::new(x, y))
+        .filter(|offset| *offset != IVec2::ZERO)
+        .collect()
+}
+
+/// Keeps a ghost for each edge a sprite hangs over, following the sprite and its children around.
+#[allow(clippy::type_complexity)]
+fn ghost_system(
+    mut commands: Commands,
+    resolution: Res<Resolution>,
+    q_source: Query<
+        (
+            Entity,
+            &Transform,
+            &Segments,
+            &Mesh2dHandle,
+            &Handle<LineMaterial>,
+            &Visibility,
+            Option<&Children>,
+        ),
+        (Without<Parent>, Without<Ghost>, Without<GhostChild>),
+    >,
+    q_source_child: Query<
+        (
+            &Transform,
+            &Mesh2dHandle,
+            &Handle<LineMaterial>,
+            &Visibility,
+        ),
+        (With<Parent>, Without<Ghost>, Without<GhostChild>),
+    >,
+    mut q_ghost: Query<(Entity, &Ghost, &mut Transform, &mut Visibility)>,
+    mut q_ghost_child: Query<(&GhostChild, &mut Transform, &mut Visibility), Without<Ghost>>,
+) {
+    let playfield = Vec2::new(resolution.width, resolution.height);
+    let ghost_transform = |transform: &Transform, offset: IVec2| {
+        transform
+            .with_translation(transform.translation + (offset.as_vec2() * playfield).extend(0.0))
+    };
+
+    let mut needed = HashSet::new();
+    for (entity, transform, Segments(segments), ..) in q_source.iter() {
+        let points = segments
+            .iter()
+            .flat_map(|(a, b)| [*a, *b])
+            .map(|point| transform.transform_point(point.extend(0.0)).truncate());
+        let (min, max) = points.fold(
+            (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |(min, max), point| (min.min(point), max.max(point)),
+        );
+
+        needed.extend(
+            wrap_offsets(min, max, playfield)
+                .into_iter()
+                .map(|offset| (entity, offset)),
+        );
+    }
+
+    for (ghost_entity, ghost, mut transform, mut visibility) in q_ghost.iter_mut() {
+        let source = q_source.get(ghost.source);
+        match source {
+            Ok((_, source_transform, _, _, _, source_visibility, _))
+                if needed.remove(&(ghost.source, ghost.offset)) =>
+            {
+                *transform = ghost_transform(source_transform, ghost.offset);
+                *visibility = *source_visibility;
+            }
+            _ => commands.entity(ghost_entity).despawn_recursive(),
+        }
+    }
+
+    for (ghost_child, mut transform, mut visibility) in q_ghost_child.iter_mut() {
+        if let Ok((source_transform, _, _, source_visibility)) =
+            q_source_child.get(ghost_child.source)
+        {
+            *transform = *source_transform;
+            *visibility = *source_visibility;
+        }
+    }
+
+    for (source, offset) in needed {
+        let Ok((_, transform, _, mesh, material, visibility, children)) = q_source.get(source)
+        else {
+            continue;
+        };
+
+        commands
+            .spawn((
+                Ghost { source, offset },
+                MaterialMesh2dBundle {
+                    mesh: mesh.clone(),
+                    material: material.clone(),
+                    transform: ghost_transform(transform, offset),
+                    visibility: *visibility,
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                for child in children.into_iter().flatten() {
+                    let Ok((transform, mesh, material, visibility)) = q_source_child.get(*child)
+                    else {
+                        continue;
+                    };
+
+                    parent.spawn((
+                        GhostChild { source: *child },
+                        MaterialMesh2dBundle {
+                            mesh: mesh.clone(),
+                            material: material.clone(),
+                            transform: *transform,
+                            visibility: *visibility,
+                            ..default()
+                        },
+                    ));
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYFIELD: Vec2 = Vec2::new(800.0, 600.0);
+
+    fn offsets(center: Vec2, half_size: f32) -> Vec<(i32, i32)> {
+        let mut offsets = wrap_offsets(center - half_size, center + half_size, PLAYFIELD)
+            .into_iter()
+            .map(|offset| (offset.x, offset.y))
+            .collect::<Vec<_>>();
+        offsets.sort_unstable();
+        offsets
+    }
+
+    #[test]
+    fn inside_the_playfield() {
+        assert!(offsets(Vec2::ZERO, 10.0).is_empty());
+        // touching the edges isn't hanging over them
+        assert!(offsets(Vec2::new(390.0, 290.0), 10.0).is_empty());
+    }
+
+    #[test]
+    fn over_an_edge() {
+        // drawn again on the opposite side
+        assert_eq!(offsets(Vec2::new(395.0, 0.0), 10.0), vec![(-1, 0)]);
+        assert_eq!(offsets(Vec2::new(-395.0, 0.0), 10.0), vec![(1, 0)]);
+        assert_eq!(offsets(Vec2::new(0.0, 295.0), 10.0), vec![(0, -1)]);
+        assert_eq!(offsets(Vec2::new(0.0, -295.0), 10.0), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn over_a_corner() {
+        assert_eq!(
+            offsets(Vec2::new(395.0, -295.0), 10.0),
+            vec![(-1, 0), (-1, 1), (0, 1)]
+        );
+        assert_eq!(
+            offsets(Vec2::new(-395.0, 295.0), 10.0),
+            vec![(0, -1), (1, -1), (1, 0)]
+        );
+    }
+
+    #[test]
+    fn larger_than_the_playfield() {
+        assert_eq!(
+            offsets(Vec2::ZERO, 500.0),
+            vec![
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1)
+            ]
+        );
+    }
+}
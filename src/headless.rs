@@ -0,0 +1,133 @@
+//! Runs the game without a window, as fast as possible, and writes SVGs along the way.
+//!
+//! Enabled by setting `RUSTEROIDS_HEADLESS` to the number of fixed steps to run. The input comes
+//! from the replay or script given by `RUSTEROIDS_REPLAY` or `RUSTEROIDS_SCRIPT` (see
+//! [`crate::replay`]), the ships stay idle otherwise. SVGs are written to the directory given by
+//! `RUSTEROIDS_HEADLESS_OUTPUT` (the current one by default) at the replay's export ticks, or
+//! after the last tick if there are none.
+
+use crate::line_sprite::LineMaterial;
+use crate::replay::{start_playback_system, Playback, Recording, Replay, TickInput};
+use crate::resources::{GameMode, GameRng};
+use crate::svg_export::{svg_export_system, SvgExportSettings};
+use crate::Resolution;
+use bevy::app::AppExit;
+use bevy::asset::AssetPlugin;
+use bevy::input::InputPlugin;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+
+/// Stands in for the window and render plugins, and drives the fixed steps.
+pub struct HeadlessPlugin {
+    /// Number of fixed steps to run before exiting.
+    pub ticks: u32,
+    pub output_dir: String,
+}
+
+impl HeadlessPlugin {
+    pub fn from_env() -> Option<Self> {
+        let ticks = std::env::var("RUSTEROIDS_HEADLESS").ok()?.parse().ok()?;
+        let output_dir =
+            std::env::var("RUSTEROIDS_HEADLESS_OUTPUT").unwrap_or_else(|_| ".".to_owned());
+
+        Some(Self { ticks, output_dir })
+    }
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            LogPlugin::default(),
+            AssetPlugin::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+            InputPlugin,
+        ))
+        // line sprites still carry their mesh and material, which are never rendered
+        .add_asset::<Mesh>()
+        .add_asset::<LineMaterial>()
+        .insert_resource(HeadlessRun {
+            ticks: self.ticks,
+            output_dir: self.output_dir.clone(),
+        })
+        .add_systems(
+            Startup,
+            (headless_start_system.before(start_playback_system),),
+        )
+        .add_systems(First, (headless_step_system,))
+        .add_systems(
+            Last,
+            (
+                headless_export_path_system.before(svg_export_system),
+                headless_exit_system.after(svg_export_system),
+            ),
+        );
+    }
+}
+
+#[derive(Resource, Debug)]
+struct HeadlessRun {
+    ticks: u32,
+    output_dir: String,
+}
+
+/// Plays back the given input, or none at all, and makes sure something gets exported.
+fn headless_start_system(
+    run: Res<HeadlessRun>,
+    mode: Res<GameMode>,
+    resolution: Res<Resolution>,
+    game_rng: Res<GameRng>,
+    mut replay: ResMut<Replay>,
+    mut time: ResMut<Time>,
+) {
+    // the fixed steps are driven by `headless_step_system` instead of the wall clock
+    time.pause();
+
+    let playback = replay.playback.get_or_insert_with(|| {
+        Playback::new(Recording {
+            seed: game_rng.seed,
+            mode: *mode,
+            resolution: Vec2::new(resolution.width, resolution.height),
+            ticks: vec![TickInput::default(); run.ticks as usize],
+            ..default()
+        })
+    });
+
+    let last_tick = run.ticks.min(playback.recording.len()).saturating_sub(1);
+    if playback.recording.exports.is_empty() {
+        playback.recording.exports.insert(last_tick);
+    }
+}
+
+/// Runs exactly one fixed step per frame.
+fn headless_step_system(mut fixed_time: ResMut<FixedTime>) {
+    let period = fixed_time.period;
+    fixed_time.tick(period);
+}
+
+/// Names each SVG after the tick it shows.
+fn headless_export_path_system(
+    run: Res<HeadlessRun>,
+    replay: Res<Replay>,
+    mut svg_export_settings: ResMut<SvgExportSettings>,
+) {
+    if svg_export_settings.run_export {
+        let tick = replay.tick.saturating_sub(1);
+        svg_export_settings.export_path = format!("{}/rusteroids_{:06}.svg", run.output_dir, tick);
+        info!("exporting {}", svg_export_settings.export_path);
+    }
+}
+
+/// Exits once the requested number of ticks has run, or the game is over.
+fn headless_exit_system(
+    run: Res<HeadlessRun>,
+    replay: Res<Replay>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if replay.playback.is_none() {
+        info!("game over after {} ticks", replay.tick);
+        exit.send(AppExit);
+    } else if replay.tick >= run.ticks {
+        exit.send(AppExit);
+    }
+}
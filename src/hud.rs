@@ -0,0 +1,227 @@
+use crate::components::{LifeTime, Player, PowerUps, Ship};
+use crate::events::{WaveClearedEvent, WaveStartEvent};
+use crate::line_sprite::{LineMaterial, LineSpriteBundleBuilder, Shape};
+use crate::resources::{Lives, PowerUpTable, Score, Wave};
+use crate::stroke_font::{HAlign, StrokeText, VAlign};
+use crate::svg_export::SvgText;
+use crate::Resolution;
+use bevy::prelude::*;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (hud_system, wave_banner_system));
+    }
+}
+
+/// Marks the line sprites making up the HUD, so they can be rebuilt when their value changes.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudElement {
+    Score(Player),
+    Wave,
+    Lives(Player),
+    PowerUps(Player),
+}
+
+/// Transient text announcing wave starts and completions.
+#[derive(Component)]
+struct WaveBanner;
+
+const MARGIN: f32 = 20.0;
+const TEXT_SIZE: f32 = 20.0;
+const BANNER_SIZE: f32 = 24.0;
+const BANNER_DURATION: f32 = 1.5;
+const LIFE_ICON_SCALE: f32 = 0.6;
+const LIFE_ICON_ADVANCE: f32 = 16.0;
+const POWER_UP_TEXT_SIZE: f32 = 12.0;
+/// Horizontal distance between the columns showing each player's score, lives and power-ups.
+const PLAYER_COLUMN_WIDTH: f32 = 200.0;
+
+#[allow(clippy::too_many_arguments)]
+fn hud_system(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    resolution: Res<Resolution>,
+    score: Res<Score>,
+    wave: Res<Wave>,
+    lives: Res<Lives>,
+    table: Res<PowerUpTable>,
+    mut power_up_texts: Local<Vec<String>>,
+    q_power_ups: Query<(&Player, &PowerUps), With<Ship>>,
+    query: Query<(Entity, &HudElement)>,
+) {
+    let players = (0..score.0.len()).map(Player).collect::<Vec<_>>();
+
+    // one line per active power-up, with its remaining time in whole seconds
+    let texts = players
+        .iter()
+        .map(|player| {
+            q_power_ups
+                .iter()
+                .find(|(p, _)| *p == player)
+                .map(|(_, power_ups)| {
+                    power_ups
+                        .active
+                        .iter()
+                        .filter_map(|(kind, timer)| {
+                            let spec = table.get(*kind)?;
+                            let remaining = timer.remaining_secs().ceil();
+                            Some(format!("{} {}", spec.label, remaining))
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    let power_ups_changed = texts != *power_up_texts;
+    *power_up_texts = texts;
+
+    // the number of players changes when a new game starts, clear everything then
+    if score.is_changed() {
+        for (entity, element) in query.iter() {
+            if !players.iter().any(|player| match element {
+                HudElement::Score(p) | HudElement::Lives(p) | HudElement::PowerUps(p) => {
+                    p == player
+                }
+                HudElement::Wave => true,
+            }) {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+
+    let top_left = Vec2::new(-resolution.width / 2.0, resolution.height / 2.0);
+    let top_right = Vec2::new(resolution.width / 2.0, resolution.height / 2.0);
+
+    let elements = players
+        .iter()
+        .flat_map(|player| {
+            [
+                HudElement::Score(*player),
+                HudElement::Lives(*player),
+                HudElement::PowerUps(*player),
+            ]
+        })
+        .chain([HudElement::Wave]);
+
+    for element in elements {
+        let changed = resolution.is_changed()
+            || match element {
+                HudElement::Score(_) => score.is_changed(),
+                HudElement::Wave => wave.is_changed(),
+                HudElement::Lives(_) => lives.is_changed(),
+                HudElement::PowerUps(_) => power_ups_changed,
+            };
+        if !changed {
+            continue;
+        }
+
+        for (entity, _) in query.iter().filter(|(_, e)| **e == element) {
+            commands.entity(entity).despawn();
+        }
+
+        let column = |player: Player| {
+            top_left + Vec2::new(MARGIN + player.0 as f32 * PLAYER_COLUMN_WIDTH, 0.0)
+        };
+
+        let text = |text: StrokeText| (text.segments(), Some(SvgText(text.paths())));
+
+        let ((segments, svg_text), position) = match element {
+            HudElement::Score(player) => (
+                text(
+                    StrokeText::new(score.0[player.0].to_string())
+                        .size(TEXT_SIZE)
+                        .align(HAlign::Left, VAlign::Top),
+                ),
+                column(player) + Vec2::new(0.0, -MARGIN),
+            ),
+            HudElement::Wave => (
+                text(
+                    StrokeText::new(format!("WAVE {}", wave.0))
+                        .size(TEXT_SIZE)
+                        .align(HAlign::Right, VAlign::Top),
+                ),
+                top_right + Vec2::new(-MARGIN, -MARGIN),
+            ),
+            HudElement::Lives(player) => (
+                (lives_segments(lives.get(player)), None),
+                column(player) + Vec2::new(0.0, -2.0 * MARGIN - 2.0 * TEXT_SIZE),
+            ),
+            HudElement::PowerUps(player) => (
+                text(
+                    StrokeText::new(power_up_texts[player.0].as_str())
+                        .size(POWER_UP_TEXT_SIZE)
+                        .align(HAlign::Left, VAlign::Top),
+                ),
+                column(player) + Vec2::new(0.0, -3.0 * MARGIN - 3.0 * TEXT_SIZE),
+            ),
+        };
+
+        let mut sprite = commands.spawn((
+            element,
+            LineSpriteBundleBuilder::new(Shape::LineString(vec![]))
+                .add_segments(segments)
+                .transform(Transform::from_translation(position.extend(0.0)))
+                .build(&mut meshes, &mut materials),
+        ));
+        if let Some(svg_text) = svg_text {
+            sprite.insert(svg_text);
+        }
+    }
+}
+
+fn wave_banner_system(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    mut start_receiver: EventReader<WaveStartEvent>,
+    mut cleared_receiver: EventReader<WaveClearedEvent>,
+    query: Query<Entity, With<WaveBanner>>,
+) {
+    let text = cleared_receiver
+        .iter()
+        .map(|e| format!("WAVE {} CLEARED", e.wave))
+        .chain(start_receiver.iter().map(|e| format!("WAVE {}", e.wave)))
+        .last();
+
+    let Some(text) = text else {
+        return;
+    };
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let text = StrokeText::new(text)
+        .size(BANNER_SIZE)
+        .align(HAlign::Center, VAlign::Center);
+
+    commands.spawn((
+        WaveBanner,
+        LifeTime(Timer::from_seconds(BANNER_DURATION, TimerMode::Once)),
+        SvgText(text.paths()),
+        LineSpriteBundleBuilder::new(Shape::LineString(vec![]))
+            .add_segments(text.segments())
+            .build(&mut meshes, &mut materials),
+    ));
+}
+
+/// One small ship icon per remaining life, with the origin at the bottom-left corner.
+fn lives_segments(lives: u32) -> Vec<(Vec2, Vec2)> {
+    let icon = [
+        Vec2::new(-10.0, -5.0),
+        Vec2::new(10.0, -5.0),
+        Vec2::new(0.0, 15.0),
+    ]
+    .map(|v| (v + Vec2::new(10.0, 5.0)) * LIFE_ICON_SCALE);
+
+    (0..lives)
+        .flat_map(|i| {
+            let offset = Vec2::new(i as f32 * LIFE_ICON_ADVANCE, 0.0);
+            [(0, 1), (1, 2), (2, 0)].map(|(a, b)| (icon[a] + offset, icon[b] + offset))
+        })
+        .collect()
+}
@@ -0,0 +1,183 @@
+use crate::actions::Action;
+use crate::components::{Hyperspace, HyperspaceEffect, LifeTime, Player, Ship, Speed, Thruster};
+use crate::events::ShipKillEvent;
+use crate::game_state::GameState;
+use crate::line_sprite::{LineMaterial, LineSpriteBundleBuilder, Shape};
+use crate::resources::{ActionBuffer, GameRng, Lives, ShipRespawn};
+use crate::systems::destroy_ship;
+use crate::{Resolution, TIME_STEP};
+use bevy::prelude::*;
+use rand::Rng;
+use std::time::Duration;
+
+pub struct HyperspacePlugin;
+
+impl Plugin for HyperspacePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                hyperspace_jump_system,
+                hyperspace_system,
+                hyperspace_effect_system,
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Keeps the ship away from the very edge of the playfield when picking a destination.
+const DESTINATION_MARGIN: f32 = 30.0;
+
+const EFFECT_RAY_COUNT: usize = 12;
+const EFFECT_INNER_RADIUS: f32 = 10.0;
+const EFFECT_OUTER_RADIUS: f32 = 18.0;
+
+fn spawn_effect(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<LineMaterial>>,
+    effect: HyperspaceEffect,
+    position: Vec2,
+    duration: f32,
+) {
+    let rays = (0..EFFECT_RAY_COUNT).map(|i| {
+        let direction =
+            Vec2::from_angle(i as f32 / EFFECT_RAY_COUNT as f32 * std::f32::consts::TAU);
+        (
+            direction * EFFECT_INNER_RADIUS,
+            direction * EFFECT_OUTER_RADIUS,
+        )
+    });
+
+    commands.spawn((
+        effect,
+        LifeTime(Timer::from_seconds(duration, TimerMode::Once)),
+        LineSpriteBundleBuilder::new(Shape::LineString(vec![]))
+            .add_segments(rays)
+            .transform(Transform::from_translation(position.extend(0.0)))
+            .build(meshes, materials),
+    ));
+}
+
+/// Sends ships to a random location, where they reappear after a short while.
+#[allow(clippy::type_complexity)]
+pub fn hyperspace_jump_system(
+    mut buffer: ResMut<ActionBuffer>,
+    mut game_rng: ResMut<GameRng>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    resolution: Res<Resolution>,
+    mut q_ship: Query<
+        (
+            Entity,
+            &Ship,
+            &Player,
+            &mut Transform,
+            &mut Speed,
+            &mut Thruster,
+            &mut Visibility,
+        ),
+        Without<Hyperspace>,
+    >,
+) {
+    let jumping = buffer.take_just_pressed(Action::Hyperspace);
+    let rng = &mut game_rng.rng;
+    let half_width = (resolution.width / 2.0 - DESTINATION_MARGIN).max(1.0);
+    let half_height = (resolution.height / 2.0 - DESTINATION_MARGIN).max(1.0);
+
+    for (entity, ship, player, mut transform, mut speed, mut thruster, mut visibility) in
+        q_ship.iter_mut()
+    {
+        if !jumping.contains(player) {
+            continue;
+        }
+
+        let destination = Vec2::new(
+            rng.gen_range(-half_width..half_width),
+            rng.gen_range(-half_height..half_height),
+        );
+
+        spawn_effect(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            HyperspaceEffect::Departure,
+            transform.translation.truncate(),
+            ship.hyperspace_duration,
+        );
+        spawn_effect(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            HyperspaceEffect::Arrival,
+            destination,
+            ship.hyperspace_duration,
+        );
+
+        transform.translation = destination.extend(transform.translation.z);
+        speed.0 = Vec2::ZERO;
+        thruster.active = false;
+        *visibility = Visibility::Hidden;
+
+        commands
+            .entity(entity)
+            .insert(Hyperspace(Timer::from_seconds(
+                ship.hyperspace_duration,
+                TimerMode::Once,
+            )));
+    }
+}
+
+/// Brings the ship back out of hyperspace, unless the jump goes wrong.
+fn hyperspace_system(
+    mut commands: Commands,
+    mut lives: ResMut<Lives>,
+    mut respawn: ResMut<ShipRespawn>,
+    mut game_rng: ResMut<GameRng>,
+    mut kill_sender: EventWriter<ShipKillEvent>,
+    mut q_ship: Query<(
+        Entity,
+        &Ship,
+        &Player,
+        &Transform,
+        &mut Hyperspace,
+        &mut Visibility,
+    )>,
+) {
+    let rng = &mut game_rng.rng;
+
+    for (entity, ship, player, transform, mut hyperspace, mut visibility) in q_ship.iter_mut() {
+        hyperspace.0.tick(Duration::from_secs_f32(TIME_STEP));
+        if !hyperspace.0.finished() {
+            continue;
+        }
+
+        if rng.gen_bool(ship.hyperspace_failure_chance) {
+            destroy_ship(
+                &mut commands,
+                entity,
+                *player,
+                transform,
+                None,
+                &mut lives,
+                &mut respawn,
+                &mut kill_sender,
+            );
+        } else {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<Hyperspace>();
+        }
+    }
+}
+
+fn hyperspace_effect_system(mut query: Query<(&HyperspaceEffect, &LifeTime, &mut Transform)>) {
+    for (effect, life_time, mut transform) in query.iter_mut() {
+        let scale = match effect {
+            HyperspaceEffect::Departure => 1.0 + 2.0 * life_time.0.percent(),
+            HyperspaceEffect::Arrival => 3.0 * life_time.0.percent_left(),
+        };
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
+}
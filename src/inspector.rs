@@ -1,3 +1,4 @@
+use crate::actions::{Action, ActionState};
 use bevy::prelude::*;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
@@ -8,11 +9,8 @@ fn inspector_visible(visible: Res<InspectorVisible>) -> bool {
     visible.0
 }
 
-fn inspector_show_hide_system(
-    mut visible: ResMut<InspectorVisible>,
-    keyboard_input: Res<Input<KeyCode>>,
-) {
-    if keyboard_input.just_pressed(KeyCode::F1) {
+fn inspector_show_hide_system(mut visible: ResMut<InspectorVisible>, actions: Res<ActionState>) {
+    if actions.just_pressed(Action::ToggleInspector) {
         visible.0 = !visible.0;
     }
 }
@@ -6,7 +6,7 @@ use bevy::sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle};
 use geo::coord;
 
 /// Describes the exterior shape of a sprite, for the purpose of rendering and collision detection.
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
 pub enum Shape {
     Polygon(Vec<Vec2>),
     LineString(Vec<Vec2>),
@@ -39,6 +39,14 @@ impl Shape {
             }
         }
     }
+
+    /// Segments drawing the shape, which come first in a line sprite's [`Segments`].
+    pub fn segments(&self) -> Vec<(Vec2, Vec2)> {
+        match self {
+            Self::Polygon(vertices) => line_to_segment(vertices, true),
+            Self::LineString(vertices) => line_to_segment(vertices, false),
+        }
+    }
 }
 
 fn vertices_to_coords(vertices: &[Vec2], transform: &Transform) -> Vec<geo::Coord<f32>> {
@@ -51,6 +59,10 @@ fn vertices_to_coords(vertices: &[Vec2], transform: &Transform) -> Vec<geo::Coor
         .collect()
 }
 
+/// Line segments making up a sprite, in local coordinates, starting with those of its [`Shape`].
+#[derive(Component, Debug, Clone, Default)]
+pub struct Segments(pub Vec<(Vec2, Vec2)>);
+
 // ================
 
 pub struct LineSpritePlugin;
@@ -64,6 +76,7 @@ impl Plugin for LineSpritePlugin {
 #[derive(Bundle)]
 pub struct LineSpriteBundle {
     shape: Shape,
+    segments: Segments,
     material: MaterialMesh2dBundle<LineMaterial>,
 }
 
@@ -76,19 +89,13 @@ pub struct LineSpriteBundleBuilder {
 impl LineSpriteBundleBuilder {
     /// Create a new line sprite bundle builder with the provided shape.
     pub fn new(shape: Shape) -> Self {
-        let segments = match &shape {
-            Shape::Polygon(vertices) => line_to_segment(vertices, true),
-            Shape::LineString(vertices) => line_to_segment(vertices, false),
-        };
-
         Self {
+            segments: shape.segments(),
             shape,
-            segments,
             transform: Transform::default(),
         }
     }
 
-    #[allow(dead_code)]
     pub fn add_line_string(mut self, line: impl IntoIterator<Item = Vec2>) -> Self {
         self.segments.extend(line_to_segment(
             &line.into_iter().collect::<Vec<_>>(),
@@ -115,7 +122,7 @@ impl LineSpriteBundleBuilder {
     ) -> LineSpriteBundle {
         let lines = self
             .segments
-            .into_iter()
+            .iter()
             .map(|(a, b)| (a.extend(0.0), b.extend(0.0)))
             .collect::<Vec<_>>();
         let material_bundle = MaterialMesh2dBundle {
@@ -129,6 +136,7 @@ impl LineSpriteBundleBuilder {
 
         LineSpriteBundle {
             shape: self.shape,
+            segments: Segments(self.segments),
             material: material_bundle,
         }
     }
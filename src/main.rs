@@ -1,25 +1,57 @@
+mod actions;
 mod asteroids;
+mod camera;
+mod collisions;
 mod components;
 mod events;
+mod game_state;
+mod ghosts;
+mod headless;
+mod hud;
+mod hyperspace;
 mod inspector;
 mod line_sprite;
+mod power_ups;
+mod replay;
+mod resources;
+mod saucer;
+mod stroke_font;
 mod svg_export;
 mod systems;
+mod touch;
 
+use crate::actions::ActionPlugin;
 use crate::asteroids::AsteroidMakerPlugin;
-use crate::components::{Flame, Ship, Speed, Thruster};
-use crate::events::{AsteroidKillEvent, AsteroidSpawnEvent};
-use crate::line_sprite::{LineMaterial, LineSpriteBundleBuilder, LineSpritePlugin, Shape};
+use crate::camera::CameraPlugin;
+use crate::collisions::{asteroid_collision_system, CollisionPlugin};
+use crate::events::{
+    AsteroidKillEvent, AsteroidSpawnEvent, ShipKillEvent, WaveClearedEvent, WaveStartEvent,
+};
+use crate::line_sprite::LineSpritePlugin;
+use crate::resources::{
+    ActionBuffer, AsteroidSplitting, GameRng, Lives, Score, ShipRespawn, Wave, WaveDirector,
+};
 use crate::systems::{
-    asteroid_birth_system, asteroid_kill_system, basic_rotation_speed_system, basic_speed_system,
-    explode_asteroid, keyboard_input_system, life_time_system, ship_motion_system,
-    spawn_asteroids_system, spawn_missiles_system, wrap_positions,
+    action_buffer_system, asteroid_birth_system, asteroid_kill_system, basic_rotation_speed_system,
+    basic_speed_system, debris_system, explode_asteroid, life_time_system, score_system,
+    ship_input_system, ship_kill_system, ship_motion_system, ship_respawn_system,
+    spawn_missiles_system, wave_director_system, wrap_positions,
 };
+use bevy::ecs::schedule::ExecutorKind;
 use bevy::prelude::*;
-use bevy::window::{WindowResized, WindowResolution};
+use bevy::window::WindowResolution;
 
+use crate::game_state::{GameState, GameStatePlugin};
+use crate::ghosts::GhostPlugin;
+use crate::headless::HeadlessPlugin;
+use crate::hud::HudPlugin;
+use crate::hyperspace::HyperspacePlugin;
 use crate::inspector::InspectorPlugin;
+use crate::power_ups::PowerUpPlugin;
+use crate::replay::ReplayPlugin;
+use crate::saucer::SaucerPlugin;
 use crate::svg_export::SvgExportPlugin;
+use crate::touch::TouchPlugin;
 
 const TIME_STEP: f32 = 1.0 / 60.0;
 
@@ -29,49 +61,7 @@ const INITIAL_HEIGHT: f32 = 600.0;
 #[derive(Resource)]
 struct FrameTimer(Timer);
 
-fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<LineMaterial>>,
-    mut msaa: ResMut<Msaa>,
-) {
-    commands.spawn(Camera2dBundle::default());
-
-    // Spawn a list of lines with start and end points for each lines
-    let parent = commands
-        .spawn((
-            Ship::default(),
-            Speed::default(),
-            Thruster::default(),
-            LineSpriteBundleBuilder::new(Shape::from_vertices(
-                [
-                    Vec2::new(-10.0, -5.0),
-                    Vec2::new(10.0, -5.0),
-                    Vec2::new(0.0, 15.0),
-                ],
-                true,
-            ))
-            .build(&mut meshes, &mut materials),
-        ))
-        .id();
-
-    let child = commands
-        .spawn((
-            Flame,
-            LineSpriteBundleBuilder::new(Shape::from_vertices(
-                [
-                    Vec2::new(-5.0, -8.0),
-                    Vec2::new(5.0, -8.0),
-                    Vec2::new(0.0, -13.0),
-                ],
-                true,
-            ))
-            .build(&mut meshes, &mut materials),
-        ))
-        .id();
-
-    commands.entity(parent).push_children(&[child]);
-
+fn setup(mut msaa: ResMut<Msaa>) {
     #[cfg(not(target_arch = "wasm32"))]
     {
         *msaa = Msaa::Off;
@@ -87,6 +77,10 @@ trait ResExt {
     fn as_vec3(&self) -> Vec3;
 }
 
+/// Size of the playfield, in world units.
+///
+/// It doesn't depend on the window's size: the camera scales the playfield to fit the window
+/// instead (see [`camera::PlayfieldScaling`]).
 #[derive(Resource, Debug, Default)]
 pub struct Resolution {
     pub width: f32,
@@ -99,33 +93,13 @@ impl ResExt for Resolution {
     }
 }
 
-impl ResExt for WindowResized {
-    fn as_vec3(&self) -> Vec3 {
-        Vec3::new(self.width, self.height, 0.0)
-    }
-}
-
-fn on_resize_system(
-    mut resolution: ResMut<Resolution>,
-    mut resize_reader: EventReader<WindowResized>,
-    mut query: Query<&mut Transform, With<Ship>>,
-) {
-    for e in resize_reader.iter() {
-        // Adjust position for the new resolution
-        for mut transform in query.iter_mut() {
-            transform.translation.x += 0.5 * (resolution.width - e.width);
-            transform.translation.y -= 0.5 * (resolution.height - e.height);
-        }
-
-        // When resolution is being changed
-        resolution.width = e.width;
-        resolution.height = e.height;
-    }
-}
-
 fn main() {
-    App::new()
-        .add_plugins((
+    let mut app = App::new();
+
+    if let Some(headless) = HeadlessPlugin::from_env() {
+        app.add_plugins((MinimalPlugins, headless));
+    } else {
+        app.add_plugins((
             DefaultPlugins.set(WindowPlugin {
                 primary_window: Some(Window {
                     title: "Rusteroïds".into(),
@@ -137,36 +111,74 @@ fn main() {
                 ..default()
             }),
             LineSpritePlugin,
-            SvgExportPlugin,
+            CameraPlugin,
+            GhostPlugin,
             InspectorPlugin,
-            AsteroidMakerPlugin,
+            TouchPlugin,
         ))
-        .insert_resource(ClearColor(Color::BLACK))
-        .insert_resource(FixedTime::new_from_secs(TIME_STEP))
-        .insert_resource(Resolution {
-            width: INITIAL_WIDTH,
-            height: INITIAL_HEIGHT,
-        })
-        .insert_resource(FrameTimer(Timer::from_seconds(1.0, TimerMode::Repeating)))
-        .add_systems(Startup, (setup,))
-        .add_systems(First, (spawn_missiles_system,)) // dont miss key-presses
-        .add_systems(
-            FixedUpdate,
-            (
-                keyboard_input_system.before(ship_motion_system),
-                ship_motion_system,
-                life_time_system,
-                spawn_asteroids_system.before(asteroid_birth_system),
-                asteroid_birth_system,
-                asteroid_kill_system,
-                basic_speed_system,
-                basic_rotation_speed_system,
-                on_resize_system,
-                wrap_positions,
-            ),
+        .add_systems(Startup, (setup,));
+    }
+
+    app.add_plugins((
+        ActionPlugin,
+        SvgExportPlugin,
+        AsteroidMakerPlugin,
+        CollisionPlugin,
+        HudPlugin,
+        GameStatePlugin,
+        SaucerPlugin,
+        HyperspacePlugin,
+        PowerUpPlugin,
+        ReplayPlugin,
+    ))
+    .insert_resource(ClearColor(Color::BLACK))
+    .insert_resource(FixedTime::new_from_secs(TIME_STEP))
+    .insert_resource(Resolution {
+        width: INITIAL_WIDTH,
+        height: INITIAL_HEIGHT,
+    })
+    .insert_resource(FrameTimer(Timer::from_seconds(1.0, TimerMode::Repeating)))
+    .init_resource::<Score>()
+    .init_resource::<Wave>()
+    .init_resource::<WaveDirector>()
+    .init_resource::<Lives>()
+    .init_resource::<ShipRespawn>()
+    .init_resource::<ActionBuffer>()
+    .init_resource::<GameRng>()
+    .init_resource::<AsteroidSplitting>()
+    // a fixed system order keeps the random draws, and thus the games, reproducible
+    .edit_schedule(FixedUpdate, |schedule| {
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    })
+    .add_systems(
+        First,
+        (action_buffer_system.run_if(in_state(GameState::Playing)),), // dont miss key-presses
+    )
+    .add_systems(
+        FixedUpdate,
+        (
+            ship_input_system.before(ship_motion_system),
+            ship_motion_system,
+            spawn_missiles_system.after(ship_motion_system),
+            life_time_system,
+            debris_system.after(life_time_system),
+            wave_director_system.before(asteroid_birth_system),
+            asteroid_birth_system,
+            asteroid_kill_system.after(asteroid_collision_system),
+            explode_asteroid.after(asteroid_kill_system),
+            ship_kill_system.after(asteroid_collision_system),
+            ship_respawn_system,
+            basic_speed_system,
+            basic_rotation_speed_system,
+            wrap_positions,
         )
-        .add_systems(Update, (explode_asteroid,))
-        .init_resource::<Events<AsteroidSpawnEvent>>() // no GC for these events
-        .add_event::<AsteroidKillEvent>()
-        .run();
+            .run_if(in_state(GameState::Playing)),
+    )
+    .add_systems(Update, (score_system,))
+    .init_resource::<Events<AsteroidSpawnEvent>>() // no GC for these events
+    .add_event::<AsteroidKillEvent>()
+    .add_event::<ShipKillEvent>()
+    .add_event::<WaveStartEvent>()
+    .add_event::<WaveClearedEvent>()
+    .run();
 }
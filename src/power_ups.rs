@@ -0,0 +1,127 @@
+use crate::collisions::{update_broadphase_system, Broadphase};
+use crate::components::{
+    Hyperspace, LifeTime, PierceCooldown, PowerUpPickup, PowerUps, Ship, Speed,
+};
+use crate::events::AsteroidKillEvent;
+use crate::game_state::GameState;
+use crate::line_sprite::{LineMaterial, LineSpriteBundleBuilder, Shape};
+use crate::resources::{GameRng, PowerUpTable};
+use crate::stroke_font::{HAlign, StrokeText, VAlign};
+use crate::systems::asteroid_kill_system;
+use crate::TIME_STEP;
+use bevy::prelude::*;
+use rand::Rng;
+use std::time::Duration;
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PowerUpTable>().add_systems(
+            FixedUpdate,
+            (
+                power_up_drop_system.after(asteroid_kill_system),
+                power_up_collect_system.after(update_broadphase_system),
+                power_up_timer_system,
+                pierce_cooldown_system,
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+const PICKUP_RADIUS: f32 = 10.0;
+const PICKUP_ICON_SIZE: f32 = 8.0;
+
+/// Occasionally leaves a power-up pickup behind destroyed asteroids.
+fn power_up_drop_system(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    table: Res<PowerUpTable>,
+    mut game_rng: ResMut<GameRng>,
+    mut receiver: EventReader<AsteroidKillEvent>,
+) {
+    let rng = &mut game_rng.rng;
+
+    for event in receiver.iter() {
+        // only the asteroids shot by players drop rewards, not the ones saucers break
+        if event.by.is_none() || !rng.gen_bool(table.drop_chance) {
+            continue;
+        }
+        let Some(spec) = table.choose(rng) else {
+            continue;
+        };
+
+        let shape = Shape::from_vertices(
+            [
+                Vec2::new(0.0, PICKUP_RADIUS),
+                Vec2::new(PICKUP_RADIUS, 0.0),
+                Vec2::new(0.0, -PICKUP_RADIUS),
+                Vec2::new(-PICKUP_RADIUS, 0.0),
+            ],
+            true,
+        );
+        let icon = StrokeText::new(spec.icon)
+            .size(PICKUP_ICON_SIZE)
+            .align(HAlign::Center, VAlign::Center);
+
+        commands.spawn((
+            PowerUpPickup(spec.kind),
+            Speed(event.speed * 0.5),
+            LifeTime(Timer::from_seconds(
+                table.pickup_time_to_live,
+                TimerMode::Once,
+            )),
+            LineSpriteBundleBuilder::new(shape)
+                .add_segments(icon.segments())
+                .transform(Transform::from_translation(event.transform.translation))
+                .build(&mut meshes, &mut materials),
+        ));
+    }
+}
+
+/// Applies the effect of the pickups touched by a ship.
+#[allow(clippy::type_complexity)]
+fn power_up_collect_system(
+    mut commands: Commands,
+    table: Res<PowerUpTable>,
+    mut q_ship: Query<&mut PowerUps, (With<Ship>, Without<Hyperspace>)>,
+    q_pickup: Query<(Entity, &PowerUpPickup)>,
+    broadphase: Res<Broadphase>,
+) {
+    for (entity, PowerUpPickup(kind)) in q_pickup.iter() {
+        let Some(collector) = broadphase
+            .hits(entity)
+            .find(|ship_entity| q_ship.contains(*ship_entity))
+        else {
+            continue;
+        };
+        let Ok(mut power_ups) = q_ship.get_mut(collector) else {
+            continue;
+        };
+
+        if let Some(spec) = table.get(*kind) {
+            power_ups.activate(spec.kind, spec.duration);
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+fn power_up_timer_system(mut query: Query<&mut PowerUps>) {
+    for mut power_ups in query.iter_mut() {
+        power_ups.active.retain(|_, timer| {
+            timer.tick(Duration::from_secs_f32(TIME_STEP));
+            !timer.finished()
+        });
+    }
+}
+
+fn pierce_cooldown_system(mut commands: Commands, mut query: Query<(Entity, &mut PierceCooldown)>) {
+    for (entity, mut cooldown) in query.iter_mut() {
+        cooldown.0.tick(Duration::from_secs_f32(TIME_STEP));
+        if cooldown.0.finished() {
+            commands.entity(entity).remove::<PierceCooldown>();
+        }
+    }
+}
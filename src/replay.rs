@@ -0,0 +1,860 @@
+//! Recording of the players' input, and playback of recorded games.
+//!
+//! Games are fully determined by their seed and by the content of the [`ActionBuffer`] at each
+//! fixed step. Every game is recorded, and saved to [`REPLAY_PATH`] when it ends. Setting the
+//! `RUSTEROIDS_REPLAY` environment variable to the path of such a file plays it back instead of
+//! showing the title screen. `RUSTEROIDS_SCRIPT` does the same with input written by hand, see
+//! [`Recording::from_script`].
+//!
+//! Replays export the playfield to SVG at the ticks where the player did so, and at the ticks
+//! listed in the `RUSTEROIDS_REPLAY_EXPORTS` environment variable (e.g. `1200,3600`).
+
+use crate::actions::{Action, ActionState};
+use crate::components::Player;
+use crate::game_state::{new_game, GameState, NewGame};
+use crate::hyperspace::hyperspace_jump_system;
+use crate::line_sprite::{LineMaterial, LineSpriteBundleBuilder, Shape};
+use crate::resources::{ActionBuffer, GameMode, GameRng};
+use crate::stroke_font::{HAlign, StrokeText, VAlign};
+use crate::svg_export::{NoSvgExport, SvgExportSettings};
+use crate::systems::{ship_input_system, spawn_missiles_system};
+use crate::{Resolution, INITIAL_HEIGHT, INITIAL_WIDTH, TIME_STEP};
+use bevy::prelude::*;
+use itertools::Itertools;
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Replay::from_env())
+            .add_systems(Startup, (start_playback_system,))
+            .add_systems(NewGame, (replay_new_game_system.after(new_game),))
+            .add_systems(OnEnter(GameState::GameOver), (replay_game_over_system,))
+            .add_systems(
+                FixedUpdate,
+                (replay_tick_system
+                    .before(ship_input_system)
+                    .before(spawn_missiles_system)
+                    .before(hyperspace_jump_system)
+                    .run_if(in_state(GameState::Playing)),),
+            )
+            .add_systems(
+                Update,
+                (
+                    replay_control_system,
+                    replay_restart_system.after(replay_control_system),
+                    replay_seek_system.after(replay_restart_system),
+                    replay_hud_system,
+                )
+                    .run_if(playing_back),
+            )
+            .add_systems(Update, (despawn_replay_hud.run_if(not(playing_back)),));
+    }
+}
+
+/// Where the last game is saved.
+#[cfg(not(target_arch = "wasm32"))]
+pub const REPLAY_PATH: &str = "last_game.replay";
+
+/// Distance covered by a single seek, in ticks.
+const SEEK_TICKS: u32 = 600;
+/// Maximum number of ticks run per frame while seeking.
+const SEEK_TICKS_PER_FRAME: u32 = 600;
+/// Available playback speeds.
+const SPEEDS: [f32; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+const HUD_TEXT_SIZE: f32 = 12.0;
+const HUD_MARGIN: f32 = 20.0;
+
+/// Buffered actions stored in a replay, each with a bit for pressed and one for just pressed.
+///
+/// Exports are stored separately, as they don't affect the game.
+const RECORDED_ACTIONS: [Action; 3] = [Action::Thrust, Action::Fire, Action::Hyperspace];
+
+/// Input of a single player for one fixed step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct PlayerInput {
+    /// Rotation input, in multiples of `1 / ActionBuffer::ROTATION_STEPS`.
+    rotation: i8,
+    /// Pressed and just pressed bits of the `RECORDED_ACTIONS`.
+    flags: u8,
+}
+
+/// Input of every player for one fixed step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TickInput([PlayerInput; Player::MAX]);
+
+impl TickInput {
+    fn capture(buffer: &ActionBuffer) -> Self {
+        Self(std::array::from_fn(|idx| {
+            let player = Player(idx);
+            let mut flags = 0;
+            for (bit, action) in RECORDED_ACTIONS.iter().enumerate() {
+                if buffer.pressed(player, *action) {
+                    flags |= 1 << (2 * bit);
+                }
+                if buffer.just_pressed.contains(&(player, *action)) {
+                    flags |= 1 << (2 * bit + 1);
+                }
+            }
+
+            PlayerInput {
+                rotation: (buffer.rotation(player) * ActionBuffer::ROTATION_STEPS).round() as i8,
+                flags,
+            }
+        }))
+    }
+
+    /// Replaces the content of the buffer with this input.
+    fn apply(&self, buffer: &mut ActionBuffer) {
+        *buffer = ActionBuffer::default();
+
+        for (idx, input) in self.0.iter().enumerate() {
+            let player = Player(idx);
+            for (bit, action) in RECORDED_ACTIONS.iter().enumerate() {
+                if input.flags & (1 << (2 * bit)) != 0 {
+                    buffer.pressed.insert((player, *action));
+                }
+                if input.flags & (1 << (2 * bit + 1)) != 0 {
+                    buffer.just_pressed.insert((player, *action));
+                }
+            }
+            buffer.set_rotation(player, input.rotation as f32 / ActionBuffer::ROTATION_STEPS);
+        }
+    }
+}
+
+/// Everything needed to play a game again.
+///
+/// On disk, the header is followed by runs of identical ticks, each stored as its length and a
+/// single copy of the input, which keeps files small since the input rarely changes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub seed: u64,
+    pub mode: GameMode,
+    /// Size of the playfield, which affects wrapping and spawning.
+    pub resolution: Vec2,
+    pub ticks: Vec<TickInput>,
+    /// Ticks after which the playfield is exported to SVG.
+    pub exports: BTreeSet<u32>,
+}
+
+impl Recording {
+    const MAGIC: &'static [u8; 4] = b"RSTR";
+    const VERSION: u8 = 1;
+    /// Longest game that can be loaded, a day's worth of ticks, so that corrupt files can't
+    /// exhaust the memory.
+    const MAX_TICKS: usize = (24.0 * 3600.0 / TIME_STEP) as usize;
+
+    pub fn len(&self) -> u32 {
+        self.ticks.len() as u32
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Self::MAGIC.to_vec();
+        bytes.push(Self::VERSION);
+        bytes.extend(self.seed.to_le_bytes());
+        bytes.push(match self.mode {
+            GameMode::Single => 0,
+            GameMode::Coop => 1,
+            GameMode::Versus => 2,
+        });
+        bytes.extend(self.resolution.x.to_le_bytes());
+        bytes.extend(self.resolution.y.to_le_bytes());
+        bytes.push(Player::MAX as u8);
+
+        write_varint(&mut bytes, self.exports.len() as u32);
+        let mut last = 0;
+        for tick in self.exports.iter() {
+            write_varint(&mut bytes, tick - last);
+            last = *tick;
+        }
+
+        for (length, input) in self.ticks.iter().dedup_with_count() {
+            write_varint(&mut bytes, length as u32);
+            for input in input.0.iter() {
+                bytes.extend([input.rotation as u8, input.flags]);
+            }
+        }
+
+        bytes
+    }
+
+    /// Builds a recording from lines of the form `<tick> <player> <actions>`, where the actions,
+    /// e.g. `thrust rotate_left`, are held by the player from that tick on.
+    ///
+    /// The optional `seed <seed>` and `mode <single|coop|versus>` lines set the game up, which
+    /// lasts until the tick of the last line. Malformed lines are skipped.
+    pub fn from_script(text: &str, seed: u64, resolution: Vec2) -> Self {
+        let mut recording = Self {
+            seed,
+            resolution,
+            ..default()
+        };
+        let mut changes = vec![];
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("seed") => match words.next().and_then(|seed| seed.parse().ok()) {
+                    Some(seed) => recording.seed = seed,
+                    None => warn!("malformed seed: {}", line),
+                },
+                Some("mode") => match words.next() {
+                    Some("single") => recording.mode = GameMode::Single,
+                    Some("coop") => recording.mode = GameMode::Coop,
+                    Some("versus") => recording.mode = GameMode::Versus,
+                    _ => warn!("unknown mode: {}", line),
+                },
+                Some(tick) => {
+                    let tick = tick
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|tick| (*tick as usize) < Self::MAX_TICKS);
+                    let player = words
+                        .next()
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .filter(|n| (1..=Player::MAX).contains(n));
+                    let (Some(tick), Some(player)) = (tick, player) else {
+                        warn!("malformed script line: {}", line);
+                        continue;
+                    };
+
+                    let actions = words
+                        .filter_map(|name| {
+                            let action = Action::from_name(name);
+                            if action.is_none() {
+                                warn!("unknown action: {}", name);
+                            }
+                            action
+                        })
+                        .collect::<Vec<_>>();
+                    changes.push((tick, Player(player - 1), actions));
+                }
+                None => {}
+            }
+        }
+
+        changes.sort_by_key(|(tick, _, _)| *tick);
+        let length = changes.last().map_or(0, |(tick, _, _)| tick + 1);
+
+        let mut buffer = ActionBuffer::default();
+        let mut changes = changes.into_iter().peekable();
+        for tick in 0..length {
+            buffer.just_pressed.clear();
+
+            while let Some((_, player, actions)) = changes.next_if(|(t, _, _)| *t == tick) {
+                for action in RECORDED_ACTIONS {
+                    if actions.contains(&action) {
+                        if !buffer.pressed(player, action) {
+                            buffer.just_pressed.insert((player, action));
+                        }
+                        buffer.pressed.insert((player, action));
+                    } else {
+                        buffer.pressed.remove(&(player, action));
+                    }
+                }
+
+                let left = actions.contains(&Action::RotateLeft) as i8;
+                let right = actions.contains(&Action::RotateRight) as i8;
+                buffer.set_rotation(player, (left - right) as f32);
+            }
+
+            recording.ticks.push(TickInput::capture(&buffer));
+        }
+
+        recording
+    }
+
+    /// Returns `None` if the data is not a replay, or is truncated or corrupt.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+
+        if reader.take(4)? != Self::MAGIC || reader.byte()? != Self::VERSION {
+            return None;
+        }
+
+        let seed = u64::from_le_bytes(reader.take(8)?.try_into().ok()?);
+        let mode = match reader.byte()? {
+            0 => GameMode::Single,
+            1 => GameMode::Coop,
+            2 => GameMode::Versus,
+            _ => return None,
+        };
+        let width = f32::from_le_bytes(reader.take(4)?.try_into().ok()?);
+        let height = f32::from_le_bytes(reader.take(4)?.try_into().ok()?);
+        if !(width.is_finite() && width > 0.0 && height.is_finite() && height > 0.0) {
+            return None;
+        }
+        let player_count = reader.byte()? as usize;
+
+        let mut exports = BTreeSet::new();
+        let mut last = 0u32;
+        for _ in 0..reader.varint()? {
+            last = last.checked_add(reader.varint()?)?;
+            exports.insert(last);
+        }
+
+        let mut ticks = vec![];
+        while !reader.0.is_empty() {
+            let tick_count = ticks
+                .len()
+                .checked_add(reader.varint()? as usize)
+                .filter(|count| *count <= Self::MAX_TICKS)?;
+            let mut input = TickInput::default();
+            for idx in 0..player_count {
+                let [rotation, flags] = reader.take(2)? else {
+                    return None;
+                };
+                if let Some(player_input) = input.0.get_mut(idx) {
+                    *player_input = PlayerInput {
+                        rotation: *rotation as i8,
+                        flags: *flags,
+                    };
+                }
+            }
+            ticks.resize(tick_count, input);
+        }
+
+        Some(Self {
+            seed,
+            mode,
+            resolution: Vec2::new(width, height),
+            ticks,
+            exports,
+        })
+    }
+}
+
+/// LEB128 encoding, 7 bits per byte with the high bit set on all but the last byte.
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        if self.0.len() < count {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(count);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn varint(&mut self) -> Option<u32> {
+        let mut value = 0u32;
+        for shift in (0..32).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+/// State of a replay being played back.
+#[derive(Debug)]
+pub struct Playback {
+    pub recording: Recording,
+    /// Tick to fast-forward to, exports are skipped on the way.
+    pub seek_target: Option<u32>,
+    /// Set to start the game over, when seeking backward.
+    restart: bool,
+}
+
+impl Playback {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            seek_target: None,
+            restart: false,
+        }
+    }
+}
+
+/// Records the current game, or plays back a recorded one.
+#[derive(Resource, Debug, Default)]
+pub struct Replay {
+    /// Replay being played back, the current game is recorded otherwise.
+    pub playback: Option<Playback>,
+    /// Input of the current game, when not playing back.
+    pub recording: Recording,
+    /// Fixed steps run since the current game started.
+    pub tick: u32,
+}
+
+impl Replay {
+    /// Loads the replay or the script named by the `RUSTEROIDS_REPLAY` or `RUSTEROIDS_SCRIPT`
+    /// environment variables, if any.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn from_env() -> Self {
+        let recording = if let Ok(path) = std::env::var("RUSTEROIDS_REPLAY") {
+            match std::fs::read(&path) {
+                Ok(bytes) => {
+                    let recording = Recording::decode(&bytes);
+                    if recording.is_none() {
+                        warn!("invalid replay: {}", path);
+                    }
+                    recording
+                }
+                Err(err) => {
+                    warn!("could not read {}: {}", path, err);
+                    None
+                }
+            }
+        } else if let Ok(path) = std::env::var("RUSTEROIDS_SCRIPT") {
+            match std::fs::read_to_string(&path) {
+                Ok(text) => Some(Recording::from_script(
+                    &text,
+                    GameRng::default().seed,
+                    Vec2::new(INITIAL_WIDTH, INITIAL_HEIGHT),
+                )),
+                Err(err) => {
+                    warn!("could not read {}: {}", path, err);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let Some(mut recording) = recording else {
+            return Self::default();
+        };
+
+        if let Ok(exports) = std::env::var("RUSTEROIDS_REPLAY_EXPORTS") {
+            recording.exports.extend(
+                exports
+                    .split(',')
+                    .filter_map(|tick| tick.trim().parse::<u32>().ok()),
+            );
+        }
+
+        Self {
+            playback: Some(Playback::new(recording)),
+            ..default()
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn from_env() -> Self {
+        Self::default()
+    }
+}
+
+fn playing_back(replay: Res<Replay>) -> bool {
+    replay.playback.is_some()
+}
+
+/// Skips the title screen and sets the game up as it was recorded.
+pub fn start_playback_system(
+    replay: Res<Replay>,
+    mut mode: ResMut<GameMode>,
+    mut resolution: ResMut<Resolution>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(playback) = &replay.playback else {
+        return;
+    };
+
+    *mode = playback.recording.mode;
+
+    let size = playback.recording.resolution;
+    resolution.width = size.x;
+    resolution.height = size.y;
+
+    next_state.set(GameState::Playing);
+}
+
+fn replay_new_game_system(
+    mut replay: ResMut<Replay>,
+    mode: Res<GameMode>,
+    resolution: Res<Resolution>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    replay.tick = 0;
+
+    if let Some(playback) = &replay.playback {
+        // the seed is not made the fixed one, games played after the replay get their own
+        game_rng.reseed(playback.recording.seed);
+        info!("replaying game, seed {}", game_rng.seed);
+    } else {
+        replay.recording = Recording {
+            seed: game_rng.seed,
+            mode: *mode,
+            resolution: Vec2::new(resolution.width, resolution.height),
+            ..default()
+        };
+    }
+}
+
+/// Records the input of the current fixed step, or feeds the recorded one to the game.
+fn replay_tick_system(
+    mut replay: ResMut<Replay>,
+    mut buffer: ResMut<ActionBuffer>,
+    mut svg_export_settings: ResMut<SvgExportSettings>,
+    mut fixed_time: ResMut<FixedTime>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let tick = replay.tick;
+
+    if let Some(playback) = &mut replay.playback {
+        let Some(input) = playback.recording.ticks.get(tick as usize) else {
+            *buffer = ActionBuffer::default();
+            next_state.set(GameState::GameOver);
+            return;
+        };
+        input.apply(&mut buffer);
+
+        if playback.seek_target.is_some_and(|target| tick >= target) {
+            playback.seek_target = None;
+        }
+
+        // stop for this frame, so the export shows this very tick
+        if playback.seek_target.is_none() && playback.recording.exports.contains(&tick) {
+            svg_export_settings.run_export = true;
+            while fixed_time.expend().is_ok() {}
+        }
+    } else {
+        // the export shows the state reached at the end of the previous tick
+        if !buffer.take_just_pressed(Action::Export).is_empty() && tick > 0 {
+            replay.recording.exports.insert(tick - 1);
+        }
+        let input = TickInput::capture(&buffer);
+        replay.recording.ticks.push(input);
+    }
+
+    replay.tick += 1;
+}
+
+/// Saves the game that just ended, or leaves playback when the replay is over.
+fn replay_game_over_system(mut replay: ResMut<Replay>, mut time: ResMut<Time>) {
+    if replay.playback.take().is_some() {
+        time.set_relative_speed(1.0);
+        info!("replay finished after {} ticks", replay.tick);
+        return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(err) = std::fs::write(REPLAY_PATH, replay.recording.encode()) {
+        warn!("could not write {}: {}", REPLAY_PATH, err);
+    }
+}
+
+fn replay_control_system(
+    actions: Res<ActionState>,
+    mut time: ResMut<Time>,
+    mut replay: ResMut<Replay>,
+) {
+    let tick = replay.tick;
+    let Some(playback) = &mut replay.playback else {
+        return;
+    };
+
+    let target = playback.seek_target.unwrap_or(tick);
+    if actions.just_pressed(Action::ReplayBack) {
+        playback.seek_target = Some(target.saturating_sub(SEEK_TICKS));
+        playback.restart = true;
+    }
+    if actions.just_pressed(Action::ReplayForward) {
+        playback.seek_target = Some((target + SEEK_TICKS).min(playback.recording.len()));
+    }
+
+    let speed_idx = SPEEDS
+        .iter()
+        .position(|speed| *speed >= time.relative_speed())
+        .unwrap_or(SPEEDS.len() - 1);
+    if actions.just_pressed(Action::ReplaySlower) {
+        time.set_relative_speed(SPEEDS[speed_idx.saturating_sub(1)]);
+    }
+    if actions.just_pressed(Action::ReplayFaster) {
+        time.set_relative_speed(SPEEDS[(speed_idx + 1).min(SPEEDS.len() - 1)]);
+    }
+}
+
+/// Starts the replayed game over, the only way to go back in time.
+fn replay_restart_system(world: &mut World) {
+    let restart = world
+        .resource_mut::<Replay>()
+        .playback
+        .as_mut()
+        .is_some_and(|playback| std::mem::take(&mut playback.restart));
+
+    if restart {
+        world.run_schedule(NewGame);
+    }
+}
+
+/// Runs extra fixed steps until the seek target is reached.
+fn replay_seek_system(replay: Res<Replay>, mut fixed_time: ResMut<FixedTime>) {
+    let Some(target) = replay.playback.as_ref().and_then(|p| p.seek_target) else {
+        return;
+    };
+
+    let ticks = target.saturating_sub(replay.tick).min(SEEK_TICKS_PER_FRAME);
+    fixed_time.tick(Duration::from_secs_f32(TIME_STEP) * ticks);
+}
+
+/// Shows the position in the replay and the playback speed, to help picking export ticks.
+#[derive(Component)]
+struct ReplayHud;
+
+#[allow(clippy::too_many_arguments)]
+fn replay_hud_system(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    replay: Res<Replay>,
+    time: Res<Time>,
+    resolution: Res<Resolution>,
+    mut text: Local<String>,
+    query: Query<Entity, With<ReplayHud>>,
+) {
+    let Some(playback) = &replay.playback else {
+        return;
+    };
+
+    let new_text = format!(
+        "REPLAY {} / {}  TICK {}  X{}",
+        format_ticks(replay.tick),
+        format_ticks(playback.recording.len()),
+        replay.tick,
+        time.relative_speed(),
+    );
+    if new_text == *text && !resolution.is_changed() {
+        return;
+    }
+    *text = new_text;
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let position = Vec2::new(0.0, -resolution.height / 2.0 + HUD_MARGIN);
+    commands.spawn((
+        ReplayHud,
+        NoSvgExport,
+        LineSpriteBundleBuilder::new(Shape::LineString(vec![]))
+            .add_segments(
+                StrokeText::new(text.as_str())
+                    .size(HUD_TEXT_SIZE)
+                    .align(HAlign::Center, VAlign::Bottom)
+                    .segments(),
+            )
+            .transform(Transform::from_translation(position.extend(0.0)))
+            .build(&mut meshes, &mut materials),
+    ));
+}
+
+fn despawn_replay_hud(mut commands: Commands, query: Query<Entity, With<ReplayHud>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Formats a number of ticks as minutes and seconds.
+fn format_ticks(ticks: u32) -> String {
+    let seconds = (ticks as f32 * TIME_STEP) as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(rotation: i8, flags: u8) -> TickInput {
+        let mut input = TickInput::default();
+        input.0[0] = PlayerInput { rotation, flags };
+        input
+    }
+
+    fn recording() -> Recording {
+        let idle = TickInput::default();
+        let firing = input(-127, 0b1100);
+
+        Recording {
+            seed: 0x0123_4567_89ab_cdef,
+            mode: GameMode::Versus,
+            resolution: Vec2::new(800.0, 600.0),
+            ticks: [vec![idle; 300], vec![firing; 2], vec![idle; 5]].concat(),
+            exports: BTreeSet::from([0, 150, 306]),
+        }
+    }
+
+    /// Encoded header, up to the player count, followed by the given bytes.
+    fn with_body(body: &[u8]) -> Vec<u8> {
+        let empty = Recording {
+            resolution: Vec2::new(800.0, 600.0),
+            ..default()
+        };
+        // the empty recording ends with an empty list of exports
+        let mut bytes = empty.encode();
+        bytes.pop();
+        bytes.extend(body);
+        bytes
+    }
+
+    #[test]
+    fn round_trip() {
+        let recording = recording();
+        let bytes = recording.encode();
+
+        assert_eq!(Recording::decode(&bytes), Some(recording));
+        // runs of identical ticks are stored once
+        assert!(bytes.len() < 50);
+    }
+
+    #[test]
+    fn round_trip_empty() {
+        let recording = Recording {
+            resolution: Vec2::new(1.0, 1.0),
+            ..default()
+        };
+        assert_eq!(Recording::decode(&recording.encode()), Some(recording));
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = recording().encode();
+
+        // cut in the header, the exports, or the input of a run
+        for len in 0..bytes.len() {
+            if let Some(decoded) = Recording::decode(&bytes[..len]) {
+                assert!(decoded.ticks.len() < 307, "{} bytes", len);
+            }
+        }
+        assert_eq!(Recording::decode(&bytes[..20]), None);
+        assert_eq!(Recording::decode(&bytes[..bytes.len() - 1]), None);
+    }
+
+    #[test]
+    fn malformed_header() {
+        let bytes = recording().encode();
+        let corrupt = |idx: usize, value: u8| {
+            let mut bytes = bytes.clone();
+            bytes[idx] = value;
+            Recording::decode(&bytes)
+        };
+
+        assert_eq!(corrupt(0, b'X'), None);
+        // version
+        assert_eq!(corrupt(4, 2), None);
+        // mode
+        assert_eq!(corrupt(13, 3), None);
+
+        for size in [0.0, -800.0, f32::NAN, f32::INFINITY] {
+            let mut bytes = bytes.clone();
+            bytes[14..18].copy_from_slice(&f32::to_le_bytes(size));
+            assert_eq!(Recording::decode(&bytes), None, "width {}", size);
+        }
+    }
+
+    #[test]
+    fn malformed_body() {
+        let player_count = Player::MAX;
+        let run = |length: u32| {
+            let mut bytes = vec![];
+            write_varint(&mut bytes, length);
+            bytes.extend(vec![0; 2 * player_count]);
+            bytes
+        };
+
+        // no exports, then a single run
+        let mut valid = vec![0];
+        valid.extend(run(10));
+        assert_eq!(Recording::decode(&with_body(&valid)).unwrap().len(), 10);
+
+        // run lengths adding up to more ticks than allowed
+        let mut huge = vec![0];
+        huge.extend(run(u32::MAX));
+        assert_eq!(Recording::decode(&with_body(&huge)), None);
+
+        let mut long = vec![0];
+        for _ in 0..3 {
+            long.extend(run(Recording::MAX_TICKS as u32 / 2));
+        }
+        assert_eq!(Recording::decode(&with_body(&long)), None);
+
+        // export ticks overflowing
+        let mut exports = vec![];
+        write_varint(&mut exports, 2);
+        write_varint(&mut exports, u32::MAX);
+        write_varint(&mut exports, 1);
+        assert_eq!(Recording::decode(&with_body(&exports)), None);
+
+        // varint longer than 32 bits
+        let mut overlong = vec![0];
+        overlong.extend([0xff; 6]);
+        assert_eq!(Recording::decode(&with_body(&overlong)), None);
+    }
+
+    #[test]
+    fn script() {
+        let recording = Recording::from_script(
+            "# comment\n\
+             seed 42\n\
+             mode coop\n\
+             10 2 thrust\n\
+             0 1 fire rotate_left\n\
+             3 1 fire\n\
+             5 1\n\
+             12 1 jump\n\
+             oops\n",
+            7,
+            Vec2::new(800.0, 600.0),
+        );
+
+        assert_eq!(recording.seed, 42);
+        assert_eq!(recording.mode, GameMode::Coop);
+        assert_eq!(recording.len(), 13);
+
+        let buffer_at = |tick: usize| {
+            let mut buffer = ActionBuffer::default();
+            recording.ticks[tick].apply(&mut buffer);
+            buffer
+        };
+        let (player_1, player_2) = (Player(0), Player(1));
+
+        let buffer = buffer_at(0);
+        assert!(buffer.pressed(player_1, Action::Fire));
+        assert!(buffer.just_pressed.contains(&(player_1, Action::Fire)));
+        assert_eq!(buffer.rotation(player_1), 1.0);
+
+        // still held, but not pressed again
+        let buffer = buffer_at(3);
+        assert!(buffer.pressed(player_1, Action::Fire));
+        assert!(buffer.just_pressed.is_empty());
+        assert_eq!(buffer.rotation(player_1), 0.0);
+
+        let buffer = buffer_at(5);
+        assert!(!buffer.pressed(player_1, Action::Fire));
+
+        let buffer = buffer_at(10);
+        assert!(buffer.pressed(player_2, Action::Thrust));
+        assert!(buffer.just_pressed.contains(&(player_2, Action::Thrust)));
+        assert!(buffer_at(12).pressed(player_2, Action::Thrust));
+    }
+
+    #[test]
+    fn script_ticks_are_bounded() {
+        let recording = Recording::from_script("4000000000 1 fire\n", 0, Vec2::ONE);
+        assert_eq!(recording.len(), 0);
+    }
+}
@@ -0,0 +1,560 @@
+use crate::actions::Action;
+use crate::components::{Player, PowerUpKind};
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, HashSet};
+
+// ============================================
+// Game
+
+/// Source of every random decision of a game, so that a seed and the players' inputs always
+/// lead to the same game.
+///
+/// Only systems of the single-threaded `FixedUpdate` schedule may draw from it, so that the
+/// draws always happen in the same order.
+#[derive(Resource, Debug)]
+pub struct GameRng {
+    /// Seed used for every game instead of a fresh one, e.g. to reproduce a bug report.
+    pub fixed_seed: Option<u64>,
+    /// Seed the current game was started with.
+    pub seed: u64,
+    pub rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(fixed_seed: Option<u64>) -> Self {
+        let seed = fixed_seed.unwrap_or_else(|| rand::thread_rng().gen());
+
+        Self {
+            fixed_seed,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Starts over from the fixed seed, or from a fresh one.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.fixed_seed);
+    }
+
+    /// Starts over from the given seed, without changing the fixed seed.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+}
+
+impl Default for GameRng {
+    /// The fixed seed is read from the `RUSTEROIDS_SEED` environment variable, if set.
+    fn default() -> Self {
+        Self::new(
+            std::env::var("RUSTEROIDS_SEED")
+                .ok()
+                .and_then(|seed| seed.parse().ok()),
+        )
+    }
+}
+
+/// How many players take part, and whether they can shoot each other.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameMode {
+    #[default]
+    Single,
+    Coop,
+    Versus,
+}
+
+impl GameMode {
+    pub fn player_count(&self) -> usize {
+        match self {
+            GameMode::Single => 1,
+            GameMode::Coop | GameMode::Versus => 2,
+        }
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = Player> {
+        (0..self.player_count()).map(Player)
+    }
+
+    /// Whether the players' missiles destroy the other players' ships.
+    pub fn friendly_fire(&self) -> bool {
+        *self == GameMode::Versus
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            GameMode::Single => GameMode::Coop,
+            GameMode::Coop => GameMode::Versus,
+            GameMode::Versus => GameMode::Single,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameMode::Single => "1 PLAYER",
+            GameMode::Coop => "2 PLAYERS CO-OP",
+            GameMode::Versus => "2 PLAYERS VERSUS",
+        }
+    }
+}
+
+/// Points earned by each player, indexed by player id.
+#[derive(Resource, Debug)]
+pub struct Score(pub Vec<u32>);
+
+impl Score {
+    pub fn new(mode: GameMode) -> Self {
+        Self(vec![0; mode.player_count()])
+    }
+
+    pub fn add(&mut self, player: Player, points: u32) {
+        if let Some(score) = self.0.get_mut(player.0) {
+            *score += points;
+        }
+    }
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self::new(GameMode::default())
+    }
+}
+
+/// Current wave number, starting at 1 once the first asteroids have been spawned.
+#[derive(Resource, Debug, Default)]
+pub struct Wave(pub u32);
+
+/// Decides what each wave is made of and when it starts.
+#[derive(Resource, Debug)]
+pub struct WaveDirector {
+    /// Number of category 3 asteroids in the first wave.
+    pub large_count: u32,
+    /// Additional category 3 asteroids for each subsequent wave.
+    pub large_count_increment: u32,
+    pub max_large_count: u32,
+
+    /// First wave to include category 2 asteroids, one more every other wave from there.
+    pub medium_first_wave: u32,
+    pub max_medium_count: u32,
+
+    /// Maximum asteroid speed for the first wave.
+    pub speed: f32,
+    /// Additional maximum asteroid speed for each subsequent wave.
+    pub speed_increment: f32,
+    pub max_speed: f32,
+
+    /// Asteroids are never spawned closer than this to the ship.
+    pub safe_radius: f32,
+
+    /// Delay between a wave being cleared and the next one starting.
+    pub delay: Timer,
+
+    /// Set once the current wave has been cleared and the next one is pending.
+    pub between_waves: bool,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        Self {
+            large_count: 2,
+            large_count_increment: 1,
+            max_large_count: 8,
+            medium_first_wave: 3,
+            max_medium_count: 4,
+            speed: 50.0,
+            speed_increment: 10.0,
+            max_speed: 150.0,
+            safe_radius: 150.0,
+            delay: Timer::from_seconds(2.0, TimerMode::Once),
+            between_waves: false,
+        }
+    }
+}
+
+impl WaveDirector {
+    /// Categories of the asteroids making up the given wave.
+    pub fn categories(&self, wave: u32) -> Vec<u8> {
+        let wave_idx = wave.saturating_sub(1);
+        let large_count =
+            (self.large_count + wave_idx * self.large_count_increment).min(self.max_large_count);
+        let medium_count = if wave >= self.medium_first_wave {
+            (1 + (wave - self.medium_first_wave) / 2).min(self.max_medium_count)
+        } else {
+            0
+        };
+
+        [
+            vec![3; large_count as usize],
+            vec![2; medium_count as usize],
+        ]
+        .concat()
+    }
+
+    /// Range of asteroid speeds for the given wave.
+    pub fn speed_range(&self, wave: u32) -> std::ops::Range<f32> {
+        let max_speed =
+            (self.speed + wave.saturating_sub(1) as f32 * self.speed_increment).min(self.max_speed);
+        max_speed / 2.0..max_speed
+    }
+}
+
+// ============================================
+// Input
+
+/// Ship input gathered every frame and consumed by the fixed step, so presses shorter than a
+/// fixed step are not lost.
+///
+/// This is all the fixed step knows of the players, which lets replays feed it instead.
+#[derive(Resource, Debug, Default)]
+pub struct ActionBuffer {
+    /// Actions pressed since the last fixed step consumed them.
+    pub just_pressed: HashSet<(Player, Action)>,
+    /// Actions currently held down.
+    pub pressed: HashSet<(Player, Action)>,
+    /// Current rotation input of each player, see `ActionState::rotation`.
+    pub rotation: HashMap<Player, f32>,
+}
+
+impl ActionBuffer {
+    /// Actions gathered into the buffer.
+    pub const ACTIONS: [Action; 4] = [
+        Action::Thrust,
+        Action::Fire,
+        Action::Hyperspace,
+        Action::Export,
+    ];
+
+    /// The rotation input is rounded to multiples of `1 / ROTATION_STEPS`, so that replays can
+    /// store it on a single byte and still reproduce it exactly.
+    pub const ROTATION_STEPS: f32 = 127.0;
+
+    pub fn pressed(&self, player: Player, action: Action) -> bool {
+        self.pressed.contains(&(player, action))
+    }
+
+    pub fn rotation(&self, player: Player) -> f32 {
+        self.rotation.get(&player).copied().unwrap_or(0.0)
+    }
+
+    pub fn set_rotation(&mut self, player: Player, rotation: f32) {
+        let steps = (rotation * Self::ROTATION_STEPS).round();
+        self.rotation.insert(player, steps / Self::ROTATION_STEPS);
+    }
+
+    /// Consumes the presses of `action`, returning the players who pressed it.
+    pub fn take_just_pressed(&mut self, action: Action) -> HashSet<Player> {
+        let players = self
+            .just_pressed
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(player, _)| *player)
+            .collect();
+        self.just_pressed.retain(|(_, a)| *a != action);
+        players
+    }
+}
+
+// ============================================
+// Asteroid
+
+/// Controls whether and how asteroids bounce off each other.
+#[derive(Resource, Debug)]
+pub struct AsteroidCollisions {
+    /// Game modes in which asteroids collide, they pass through each other in the others.
+    pub modes: HashSet<GameMode>,
+    /// Ratio of the relative speed after and before a collision, 1.0 being perfectly elastic.
+    pub restitution: f32,
+    pub max_rotation_speed: f32,
+}
+
+impl Default for AsteroidCollisions {
+    /// Asteroids collide in the game modes listed in the `RUSTEROIDS_ASTEROID_COLLISIONS`
+    /// environment variable, e.g. `single,coop`, and in none of them otherwise.
+    fn default() -> Self {
+        let modes = std::env::var("RUSTEROIDS_ASTEROID_COLLISIONS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|mode| match mode.trim() {
+                "single" => Some(GameMode::Single),
+                "coop" => Some(GameMode::Coop),
+                "versus" => Some(GameMode::Versus),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            modes,
+            restitution: 1.0,
+            max_rotation_speed: 3.0,
+        }
+    }
+}
+
+impl AsteroidCollisions {
+    pub fn enabled(&self, mode: GameMode) -> bool {
+        self.modes.contains(&mode)
+    }
+}
+
+/// Controls how destroyed asteroids break into smaller ones.
+#[derive(Resource, Debug)]
+pub struct AsteroidSplitting {
+    /// Cut asteroids along fracture lines through the impact point, so that the smaller ones are
+    /// pieces of the original, instead of replacing them with new random asteroids.
+    pub fracture: bool,
+    /// Pieces smaller than this fraction of the original asteroid crumble to dust.
+    pub min_area_ratio: f32,
+}
+
+impl Default for AsteroidSplitting {
+    /// Fracturing is enabled by setting the `RUSTEROIDS_FRACTURE` environment variable.
+    fn default() -> Self {
+        Self {
+            fracture: std::env::var_os("RUSTEROIDS_FRACTURE").is_some(),
+            min_area_ratio: 0.05,
+        }
+    }
+}
+
+// ============================================
+// Ship
+
+/// Number of ships left for each player, including the one currently in play.
+#[derive(Resource, Debug)]
+pub struct Lives(pub Vec<u32>);
+
+impl Lives {
+    pub const INITIAL: u32 = 3;
+
+    pub fn new(mode: GameMode) -> Self {
+        Self(vec![Self::INITIAL; mode.player_count()])
+    }
+
+    pub fn get(&self, player: Player) -> u32 {
+        self.0.get(player.0).copied().unwrap_or(0)
+    }
+
+    pub fn lose(&mut self, player: Player) {
+        if let Some(lives) = self.0.get_mut(player.0) {
+            *lives = lives.saturating_sub(1);
+        }
+    }
+}
+
+impl Default for Lives {
+    fn default() -> Self {
+        Self::new(GameMode::default())
+    }
+}
+
+/// Controls when destroyed ships are brought back.
+#[derive(Resource, Debug)]
+pub struct ShipRespawn {
+    /// Minimum delay between a ship's destruction and its respawn, for each player.
+    pub timers: Vec<Timer>,
+
+    /// A ship only respawns once no asteroid is closer than this to its spawn point.
+    pub safe_radius: f32,
+
+    /// Distance between the spawn points of the players' ships.
+    pub spacing: f32,
+}
+
+impl ShipRespawn {
+    pub fn new(mode: GameMode) -> Self {
+        Self {
+            timers: vec![Timer::from_seconds(2.0, TimerMode::Once); mode.player_count()],
+            safe_radius: 100.0,
+            spacing: 120.0,
+        }
+    }
+
+    /// Spawn point of a player's ship, players being lined up around the center.
+    pub fn spawn_point(&self, player: Player) -> Vec2 {
+        let offset = player.0 as f32 - (self.timers.len() as f32 - 1.0) / 2.0;
+        Vec2::new(offset * self.spacing, 0.0)
+    }
+}
+
+impl Default for ShipRespawn {
+    fn default() -> Self {
+        Self::new(GameMode::default())
+    }
+}
+
+// ============================================
+// Saucer
+
+/// Controls when saucers appear, and how dangerous they are.
+#[derive(Resource, Debug)]
+pub struct SaucerSpawner {
+    pub timer: Timer,
+    /// Range of the delay between two saucers, in seconds.
+    pub interval: std::ops::Range<f32>,
+    /// Increase of the probability of a small saucer for each wave after the first.
+    pub small_probability_increment: f32,
+    pub max_small_probability: f32,
+    /// Aiming error of small saucers on the first wave, in radians.
+    pub aim_error: f32,
+    /// Reduction of the aiming error for each wave after the first.
+    pub aim_error_decrement: f32,
+    pub min_aim_error: f32,
+}
+
+impl Default for SaucerSpawner {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(15.0, TimerMode::Once),
+            interval: 10.0..20.0,
+            small_probability_increment: 0.15,
+            max_small_probability: 0.8,
+            aim_error: 0.6,
+            aim_error_decrement: 0.06,
+            min_aim_error: 0.05,
+        }
+    }
+}
+
+impl SaucerSpawner {
+    pub fn small_probability(&self, wave: u32) -> f32 {
+        (wave.saturating_sub(1) as f32 * self.small_probability_increment)
+            .min(self.max_small_probability)
+    }
+
+    pub fn aim_error(&self, wave: u32) -> f32 {
+        (self.aim_error - wave.saturating_sub(1) as f32 * self.aim_error_decrement)
+            .max(self.min_aim_error)
+    }
+}
+
+// ============================================
+// Power-ups
+
+pub struct PowerUpSpec {
+    pub kind: PowerUpKind,
+    /// Name displayed on the HUD while the effect is active.
+    pub label: &'static str,
+    /// Character drawn on the pickup.
+    pub icon: char,
+    /// Duration of the effect, in seconds.
+    pub duration: f32,
+    /// Relative likelihood of this power-up being dropped.
+    pub weight: u32,
+}
+
+/// Describes the available power-ups and how often destroyed asteroids drop them.
+#[derive(Resource)]
+pub struct PowerUpTable {
+    /// Probability of a destroyed asteroid dropping a power-up.
+    pub drop_chance: f64,
+    /// Time before an uncollected pickup disappears, in seconds.
+    pub pickup_time_to_live: f32,
+    pub specs: Vec<PowerUpSpec>,
+}
+
+impl Default for PowerUpTable {
+    fn default() -> Self {
+        Self {
+            drop_chance: 0.08,
+            pickup_time_to_live: 8.0,
+            specs: vec![
+                PowerUpSpec {
+                    kind: PowerUpKind::Shield,
+                    label: "SHIELD",
+                    icon: 'S',
+                    duration: 8.0,
+                    weight: 2,
+                },
+                PowerUpSpec {
+                    kind: PowerUpKind::SpreadShot,
+                    label: "SPREAD",
+                    icon: '3',
+                    duration: 10.0,
+                    weight: 3,
+                },
+                PowerUpSpec {
+                    kind: PowerUpKind::RapidFire,
+                    label: "RAPID",
+                    icon: 'R',
+                    duration: 10.0,
+                    weight: 3,
+                },
+                PowerUpSpec {
+                    kind: PowerUpKind::Piercing,
+                    label: "PIERCE",
+                    icon: 'P',
+                    duration: 10.0,
+                    weight: 2,
+                },
+                PowerUpSpec {
+                    kind: PowerUpKind::LongRange,
+                    label: "RANGE",
+                    icon: 'L',
+                    duration: 15.0,
+                    weight: 3,
+                },
+            ],
+        }
+    }
+}
+
+impl PowerUpTable {
+    pub fn get(&self, kind: PowerUpKind) -> Option<&PowerUpSpec> {
+        self.specs.iter().find(|spec| spec.kind == kind)
+    }
+
+    /// Picks a power-up at random, according to the weights.
+    pub fn choose(&self, rng: &mut impl Rng) -> Option<&PowerUpSpec> {
+        let total_weight: u32 = self.specs.iter().map(|spec| spec.weight).sum();
+        if total_weight == 0 {
+            return None;
+        }
+
+        let mut pick = rng.gen_range(0..total_weight);
+        self.specs.iter().find(|spec| {
+            if pick < spec.weight {
+                true
+            } else {
+                pick -= spec.weight;
+                false
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wave_categories() {
+        let director = WaveDirector::default();
+
+        assert_eq!(director.categories(1), vec![3, 3]);
+        assert_eq!(director.categories(2), vec![3, 3, 3]);
+        // category 2 asteroids join at wave 3, one more every other wave
+        assert_eq!(director.categories(3), vec![3, 3, 3, 3, 2]);
+        assert_eq!(director.categories(4), vec![3, 3, 3, 3, 3, 2]);
+        assert_eq!(director.categories(5), vec![3, 3, 3, 3, 3, 3, 2, 2]);
+
+        // the number of asteroids of each category is capped
+        for wave in [20, 100] {
+            let late_wave = director.categories(wave);
+            assert_eq!(late_wave.iter().filter(|c| **c == 3).count(), 8);
+            assert_eq!(late_wave.iter().filter(|c| **c == 2).count(), 4);
+        }
+        assert_eq!(director.categories(9), director.categories(100));
+    }
+
+    #[test]
+    fn wave_speed_grows_up_to_the_cap() {
+        let director = WaveDirector::default();
+
+        assert_eq!(director.speed_range(0), 25.0..50.0);
+        assert_eq!(director.speed_range(1), 25.0..50.0);
+        assert_eq!(director.speed_range(2), 30.0..60.0);
+        assert_eq!(director.speed_range(11), 75.0..150.0);
+        assert_eq!(director.speed_range(100), 75.0..150.0);
+    }
+}
@@ -0,0 +1,267 @@
+use crate::collisions::{asteroid_collision_system, Broadphase};
+use crate::components::{
+    Asteroid, Hostile, Hyperspace, LifeTime, Missile, Player, Saucer, SaucerSize, Ship, Speed,
+};
+use crate::events::SaucerKillEvent;
+use crate::game_state::GameState;
+use crate::line_sprite::{LineMaterial, LineSpriteBundleBuilder, Segments, Shape};
+use crate::resources::{GameRng, SaucerSpawner, Wave};
+use crate::systems::{spawn_debris, spawn_missile};
+use crate::{Resolution, TIME_STEP};
+use bevy::prelude::*;
+use rand::Rng;
+use std::time::Duration;
+
+pub struct SaucerPlugin;
+
+impl Plugin for SaucerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SaucerSpawner>()
+            .add_event::<SaucerKillEvent>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    saucer_spawn_system,
+                    saucer_course_system,
+                    saucer_fire_system,
+                    saucer_kill_system.after(asteroid_collision_system),
+                    explode_saucer.after(saucer_kill_system),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Distance from the saucer center at which its missiles are spawned, before scaling.
+const MISSILE_SPAWN_OFFSET: f32 = 22.0;
+
+fn saucer_sprite(
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<LineMaterial>>,
+    transform: Transform,
+) -> impl Bundle {
+    let shape = Shape::from_vertices(
+        [
+            Vec2::new(-20.0, 0.0),
+            Vec2::new(-8.0, -7.0),
+            Vec2::new(8.0, -7.0),
+            Vec2::new(20.0, 0.0),
+            Vec2::new(8.0, 7.0),
+            Vec2::new(-8.0, 7.0),
+        ],
+        true,
+    );
+
+    LineSpriteBundleBuilder::new(shape)
+        .add_segments([(Vec2::new(-20.0, 0.0), Vec2::new(20.0, 0.0))])
+        .add_line_string([
+            Vec2::new(-8.0, 7.0),
+            Vec2::new(-5.0, 13.0),
+            Vec2::new(5.0, 13.0),
+            Vec2::new(8.0, 7.0),
+        ])
+        .transform(transform)
+        .build(meshes, materials)
+}
+
+/// Sends a saucer across the playfield every now and then, as long as asteroids are left.
+#[allow(clippy::too_many_arguments)]
+fn saucer_spawn_system(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    mut spawner: ResMut<SaucerSpawner>,
+    mut game_rng: ResMut<GameRng>,
+    wave: Res<Wave>,
+    resolution: Res<Resolution>,
+    q_saucer: Query<(), With<Saucer>>,
+    q_asteroid: Query<(), With<Asteroid>>,
+) {
+    if !q_saucer.is_empty() || q_asteroid.is_empty() {
+        return;
+    }
+
+    spawner.timer.tick(Duration::from_secs_f32(TIME_STEP));
+    if !spawner.timer.finished() {
+        return;
+    }
+
+    let rng = &mut game_rng.rng;
+    let interval = rng.gen_range(spawner.interval.clone());
+    spawner
+        .timer
+        .set_duration(Duration::from_secs_f32(interval));
+    spawner.timer.reset();
+
+    let size = if rng.gen_bool(spawner.small_probability(wave.0) as f64) {
+        SaucerSize::Small
+    } else {
+        SaucerSize::Large
+    };
+    let saucer = Saucer::new(size);
+
+    // enter from either side and leave through the other
+    let direction = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+    let position = Vec2::new(
+        -direction * resolution.width / 2.0,
+        rng.gen_range(-0.4 * resolution.height..0.4 * resolution.height),
+    );
+    let crossing_time = resolution.width / saucer.speed;
+    let speed = Vec2::new(direction * saucer.speed, 0.0);
+    let scale = Saucer::scale(size);
+
+    commands.spawn((
+        saucer,
+        Speed(speed),
+        LifeTime(Timer::from_seconds(crossing_time, TimerMode::Once)),
+        saucer_sprite(
+            &mut meshes,
+            &mut materials,
+            Transform::from_translation(position.extend(0.0))
+                .with_scale(Vec3::new(scale, scale, 1.0)),
+        ),
+    ));
+}
+
+/// Randomly switches the saucers' vertical direction.
+fn saucer_course_system(
+    mut game_rng: ResMut<GameRng>,
+    mut query: Query<(&mut Saucer, &mut Speed)>,
+) {
+    let rng = &mut game_rng.rng;
+
+    for (mut saucer, mut speed) in query.iter_mut() {
+        saucer.course_timer.tick(Duration::from_secs_f32(TIME_STEP));
+
+        if saucer.course_timer.just_finished() {
+            speed.0.y = rng.gen_range(-1..=1) as f32 * saucer.speed * 0.5;
+        }
+    }
+}
+
+/// Large saucers fire at random, small ones aim at the nearest ship with a wave-dependent accuracy.
+#[allow(clippy::too_many_arguments)]
+fn saucer_fire_system(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    spawner: Res<SaucerSpawner>,
+    mut game_rng: ResMut<GameRng>,
+    wave: Res<Wave>,
+    mut q_saucer: Query<(&mut Saucer, &Transform)>,
+    q_ship: Query<&Transform, (With<Ship>, Without<Hyperspace>)>,
+) {
+    let rng = &mut game_rng.rng;
+    for (mut saucer, saucer_transform) in q_saucer.iter_mut() {
+        saucer.fire_timer.tick(Duration::from_secs_f32(TIME_STEP));
+        if !saucer.fire_timer.just_finished() {
+            continue;
+        }
+
+        let saucer_position = saucer_transform.translation.truncate();
+        let ship_position = q_ship
+            .iter()
+            .map(|transform| transform.translation.truncate())
+            .min_by(|a, b| {
+                a.distance_squared(saucer_position)
+                    .total_cmp(&b.distance_squared(saucer_position))
+            });
+        let random_angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        let angle = match (saucer.size, ship_position) {
+            (SaucerSize::Small, Some(ship_position)) => {
+                let aim_error = spawner.aim_error(wave.0);
+                let to_ship = ship_position - saucer_position;
+                to_ship.y.atan2(to_ship.x) + rng.gen_range(-aim_error..=aim_error)
+            }
+            _ => random_angle,
+        };
+        let direction = Vec2::from_angle(angle);
+
+        let missile = Missile {
+            time_to_live: 1.2,
+            speed: 300.0,
+            ..default()
+        };
+        let speed = direction * missile.speed;
+        let transform = Transform::from_translation(
+            (saucer_position + direction * MISSILE_SPAWN_OFFSET * Saucer::scale(saucer.size))
+                .extend(0.0),
+        )
+        .with_rotation(Quat::from_rotation_arc_2d(Vec2::Y, direction));
+
+        let entity = spawn_missile(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            missile,
+            transform,
+            speed,
+        );
+        commands.entity(entity).insert(Hostile);
+    }
+}
+
+/// Destroys saucers hit by the ship's missiles, ramming the ship, or crashing into asteroids.
+#[allow(clippy::type_complexity)]
+fn saucer_kill_system(
+    q_saucer: Query<(Entity, &Transform, &Speed, &Segments, &Saucer)>,
+    q_missile: Query<(Entity, &Missile), Without<Hostile>>,
+    q_ship: Query<&Player, (With<Ship>, Without<Hyperspace>)>,
+    q_asteroid: Query<(), With<Asteroid>>,
+    broadphase: Res<Broadphase>,
+    mut commands: Commands,
+    mut kill_sender: EventWriter<SaucerKillEvent>,
+) {
+    for (saucer_entity, saucer_transform, saucer_speed, saucer_segments, saucer) in q_saucer.iter()
+    {
+        let missile_hit = broadphase
+            .hits(saucer_entity)
+            .find_map(|entity| q_missile.get(entity).ok());
+        let ship_hit = broadphase
+            .hits(saucer_entity)
+            .find_map(|entity| q_ship.get(entity).ok());
+        let asteroid_hit = broadphase
+            .hits(saucer_entity)
+            .any(|entity| q_asteroid.contains(entity));
+
+        if let Some((missile_entity, _)) = missile_hit {
+            commands.entity(missile_entity).despawn();
+        }
+
+        if missile_hit.is_some() || ship_hit.is_some() || asteroid_hit {
+            kill_sender.send(SaucerKillEvent {
+                size: saucer.size,
+                transform: *saucer_transform,
+                speed: saucer_speed.0,
+                segments: saucer_segments.0.clone(),
+                by: missile_hit
+                    .and_then(|(_, missile)| missile.owner)
+                    .or(ship_hit.copied()),
+            });
+            commands.entity(saucer_entity).despawn();
+        }
+    }
+}
+
+fn explode_saucer(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    mut game_rng: ResMut<GameRng>,
+    mut receiver: EventReader<SaucerKillEvent>,
+) {
+    let rng = &mut game_rng.rng;
+
+    for event in receiver.iter() {
+        spawn_debris(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &event.transform,
+            event.speed,
+            0.0,
+            &event.segments,
+            rng,
+        );
+    }
+}
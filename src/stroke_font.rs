@@ -0,0 +1,486 @@
+//! Minimal single-stroke vector font, in the spirit of the Hershey fonts and the original arcade
+//! lettering.
+//!
+//! Glyphs are defined on a grid where capitals are 6 units high and (mostly) 4 units wide, with
+//! the baseline at y = 0. [`StrokeText`] lays out a string with these glyphs and scales the result
+//! such that the cap height matches the requested size.
+
+use bevy::prelude::*;
+
+/// Height of capital letters in glyph units.
+const CAP_HEIGHT: f32 = 6.0;
+
+type Stroke = &'static [(f32, f32)];
+
+pub struct Glyph {
+    /// Horizontal extent of the glyph, in glyph units.
+    pub width: f32,
+    pub strokes: &'static [Stroke],
+}
+
+const fn g(width: f32, strokes: &'static [Stroke]) -> Glyph {
+    Glyph { width, strokes }
+}
+
+/// Returns the glyph for a character. Lowercase letters use the uppercase glyphs.
+pub fn glyph(c: char) -> Option<Glyph> {
+    let glyph = match c.to_ascii_uppercase() {
+        ' ' => g(4.0, &[]),
+        'A' => g(
+            4.0,
+            &[
+                &[(0., 0.), (0., 4.), (2., 6.), (4., 4.), (4., 0.)],
+                &[(0., 2.), (4., 2.)],
+            ],
+        ),
+        'B' => g(
+            4.0,
+            &[
+                &[
+                    (0., 0.),
+                    (0., 6.),
+                    (3., 6.),
+                    (4., 5.),
+                    (4., 4.),
+                    (3., 3.),
+                    (0., 3.),
+                ],
+                &[(3., 3.), (4., 2.), (4., 1.), (3., 0.), (0., 0.)],
+            ],
+        ),
+        'C' => g(4.0, &[&[(4., 0.), (0., 0.), (0., 6.), (4., 6.)]]),
+        'D' => g(
+            4.0,
+            &[&[
+                (0., 0.),
+                (0., 6.),
+                (2., 6.),
+                (4., 4.),
+                (4., 2.),
+                (2., 0.),
+                (0., 0.),
+            ]],
+        ),
+        'E' => g(
+            4.0,
+            &[
+                &[(4., 0.), (0., 0.), (0., 6.), (4., 6.)],
+                &[(0., 3.), (3., 3.)],
+            ],
+        ),
+        'F' => g(
+            4.0,
+            &[&[(0., 0.), (0., 6.), (4., 6.)], &[(0., 3.), (3., 3.)]],
+        ),
+        'G' => g(
+            4.0,
+            &[&[
+                (2., 2.),
+                (4., 2.),
+                (4., 0.),
+                (0., 0.),
+                (0., 6.),
+                (4., 6.),
+                (4., 4.),
+            ]],
+        ),
+        'H' => g(
+            4.0,
+            &[
+                &[(0., 0.), (0., 6.)],
+                &[(4., 0.), (4., 6.)],
+                &[(0., 3.), (4., 3.)],
+            ],
+        ),
+        'I' => g(
+            4.0,
+            &[
+                &[(0., 0.), (4., 0.)],
+                &[(2., 0.), (2., 6.)],
+                &[(0., 6.), (4., 6.)],
+            ],
+        ),
+        'J' => g(4.0, &[&[(0., 2.), (2., 0.), (4., 0.), (4., 6.)]]),
+        'K' => g(
+            4.0,
+            &[&[(0., 0.), (0., 6.)], &[(4., 6.), (0., 3.), (4., 0.)]],
+        ),
+        'L' => g(4.0, &[&[(0., 6.), (0., 0.), (4., 0.)]]),
+        'M' => g(4.0, &[&[(0., 0.), (0., 6.), (2., 4.), (4., 6.), (4., 0.)]]),
+        'N' => g(4.0, &[&[(0., 0.), (0., 6.), (4., 0.), (4., 6.)]]),
+        'O' => g(4.0, &[&[(0., 0.), (0., 6.), (4., 6.), (4., 0.), (0., 0.)]]),
+        'P' => g(4.0, &[&[(0., 0.), (0., 6.), (4., 6.), (4., 3.), (0., 3.)]]),
+        'Q' => g(
+            4.0,
+            &[
+                &[(0., 0.), (0., 6.), (4., 6.), (4., 2.), (2., 0.), (0., 0.)],
+                &[(2., 2.), (4., 0.)],
+            ],
+        ),
+        'R' => g(
+            4.0,
+            &[
+                &[(0., 0.), (0., 6.), (4., 6.), (4., 3.), (0., 3.)],
+                &[(1., 3.), (4., 0.)],
+            ],
+        ),
+        'S' => g(
+            4.0,
+            &[&[(0., 0.), (4., 0.), (4., 3.), (0., 3.), (0., 6.), (4., 6.)]],
+        ),
+        'T' => g(4.0, &[&[(0., 6.), (4., 6.)], &[(2., 6.), (2., 0.)]]),
+        'U' => g(4.0, &[&[(0., 6.), (0., 0.), (4., 0.), (4., 6.)]]),
+        'V' => g(4.0, &[&[(0., 6.), (2., 0.), (4., 6.)]]),
+        'W' => g(4.0, &[&[(0., 6.), (0., 0.), (2., 2.), (4., 0.), (4., 6.)]]),
+        'X' => g(4.0, &[&[(0., 0.), (4., 6.)], &[(0., 6.), (4., 0.)]]),
+        'Y' => g(
+            4.0,
+            &[&[(0., 6.), (2., 4.), (4., 6.)], &[(2., 4.), (2., 0.)]],
+        ),
+        'Z' => g(4.0, &[&[(0., 6.), (4., 6.), (0., 0.), (4., 0.)]]),
+        '0' => g(
+            4.0,
+            &[
+                &[(0., 0.), (0., 6.), (4., 6.), (4., 0.), (0., 0.)],
+                &[(0., 0.), (4., 6.)],
+            ],
+        ),
+        '1' => g(
+            4.0,
+            &[&[(1., 5.), (2., 6.), (2., 0.)], &[(1., 0.), (3., 0.)]],
+        ),
+        '2' => g(
+            4.0,
+            &[&[(0., 6.), (4., 6.), (4., 3.), (0., 3.), (0., 0.), (4., 0.)]],
+        ),
+        '3' => g(
+            4.0,
+            &[
+                &[(0., 0.), (4., 0.), (4., 6.), (0., 6.)],
+                &[(0., 3.), (4., 3.)],
+            ],
+        ),
+        '4' => g(
+            4.0,
+            &[&[(0., 6.), (0., 3.), (4., 3.)], &[(4., 6.), (4., 0.)]],
+        ),
+        '5' => g(
+            4.0,
+            &[&[(4., 6.), (0., 6.), (0., 3.), (4., 3.), (4., 0.), (0., 0.)]],
+        ),
+        '6' => g(4.0, &[&[(0., 6.), (0., 0.), (4., 0.), (4., 3.), (0., 3.)]]),
+        '7' => g(4.0, &[&[(0., 6.), (4., 6.), (4., 0.)]]),
+        '8' => g(
+            4.0,
+            &[
+                &[(0., 0.), (4., 0.), (4., 6.), (0., 6.), (0., 0.)],
+                &[(0., 3.), (4., 3.)],
+            ],
+        ),
+        '9' => g(4.0, &[&[(4., 3.), (0., 3.), (0., 6.), (4., 6.), (4., 0.)]]),
+        '.' => g(1.0, &[&[(0.5, 0.), (0.5, 0.5)]]),
+        ',' => g(1.0, &[&[(1., 1.), (0., -1.)]]),
+        ':' => g(1.0, &[&[(0.5, 1.), (0.5, 1.5)], &[(0.5, 4.), (0.5, 4.5)]]),
+        '\'' => g(1.0, &[&[(0.5, 6.), (0.5, 4.)]]),
+        '!' => g(1.0, &[&[(0.5, 6.), (0.5, 2.)], &[(0.5, 0.), (0.5, 0.5)]]),
+        '?' => g(
+            4.0,
+            &[
+                &[
+                    (0., 5.),
+                    (1., 6.),
+                    (3., 6.),
+                    (4., 5.),
+                    (4., 4.),
+                    (2., 3.),
+                    (2., 2.),
+                ],
+                &[(2., 0.), (2., 0.5)],
+            ],
+        ),
+        '-' => g(4.0, &[&[(0., 3.), (4., 3.)]]),
+        '+' => g(4.0, &[&[(0., 3.), (4., 3.)], &[(2., 1.), (2., 5.)]]),
+        '=' => g(4.0, &[&[(0., 2.), (4., 2.)], &[(0., 4.), (4., 4.)]]),
+        '/' => g(4.0, &[&[(0., 0.), (4., 6.)]]),
+        '<' => g(4.0, &[&[(4., 6.), (0., 3.), (4., 0.)]]),
+        '>' => g(4.0, &[&[(0., 6.), (4., 3.), (0., 0.)]]),
+        '(' => g(2.0, &[&[(2., 6.), (0., 4.), (0., 2.), (2., 0.)]]),
+        ')' => g(2.0, &[&[(0., 6.), (2., 4.), (2., 2.), (0., 0.)]]),
+        _ => return None,
+    };
+
+    Some(glyph)
+}
+
+/// Extra advance (usually negative) between specific pairs of characters, in glyph units.
+fn kerning(left: char, right: char) -> f32 {
+    match (left.to_ascii_uppercase(), right.to_ascii_uppercase()) {
+        ('A', 'V' | 'W' | 'Y' | 'T') | ('V' | 'W' | 'Y' | 'T', 'A') => -1.0,
+        ('L', 'T' | 'V' | 'Y') => -1.0,
+        ('T' | 'P', '.' | ',') => -1.0,
+        _ => 0.0,
+    }
+}
+
+/// Horizontal alignment of each line relative to the text origin.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Vertical alignment of the whole text block relative to the text origin.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VAlign {
+    /// Top of the first line's capitals.
+    Top,
+    Center,
+    /// Baseline of the last line.
+    #[default]
+    Bottom,
+}
+
+/// Lays out a (possibly multi-line) string with the stroke font.
+pub struct StrokeText {
+    text: String,
+    size: f32,
+    h_align: HAlign,
+    v_align: VAlign,
+    letter_spacing: f32,
+    line_spacing: f32,
+}
+
+impl StrokeText {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            size: 12.0,
+            h_align: HAlign::default(),
+            v_align: VAlign::default(),
+            letter_spacing: 2.0,
+            line_spacing: 4.0,
+        }
+    }
+
+    /// Set the cap height, in world units.
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn align(mut self, h_align: HAlign, v_align: VAlign) -> Self {
+        self.h_align = h_align;
+        self.v_align = v_align;
+        self
+    }
+
+    /// Set the space between consecutive glyphs, in glyph units (capitals are 6 units high).
+    #[allow(dead_code)]
+    pub fn letter_spacing(mut self, letter_spacing: f32) -> Self {
+        self.letter_spacing = letter_spacing;
+        self
+    }
+
+    /// Set the space between consecutive lines, in glyph units (capitals are 6 units high).
+    #[allow(dead_code)]
+    pub fn line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    fn scale(&self) -> f32 {
+        self.size / CAP_HEIGHT
+    }
+
+    /// Width of a single line, in glyph units.
+    fn line_width(&self, line: &str) -> f32 {
+        let chars = line.chars().collect::<Vec<_>>();
+        let advance: f32 = chars
+            .iter()
+            .enumerate()
+            .map(|(i, c)| self.advance(*c, chars.get(i + 1).copied()))
+            .sum();
+
+        // the last glyph doesn't need trailing spacing
+        if chars.is_empty() {
+            0.0
+        } else {
+            advance - self.letter_spacing
+        }
+    }
+
+    fn advance(&self, c: char, next: Option<char>) -> f32 {
+        let width = glyph(c).or_else(|| glyph('?')).map_or(0.0, |g| g.width);
+        width + self.letter_spacing + next.map_or(0.0, |n| kerning(c, n))
+    }
+
+    /// Height of the text block, from the top of the first line to the baseline of the last.
+    pub fn height(&self) -> f32 {
+        let line_count = self.text.lines().count().max(1) as f32;
+        (line_count * CAP_HEIGHT + (line_count - 1.0) * self.line_spacing) * self.scale()
+    }
+
+    /// Returns the laid out text as a list of polylines.
+    pub fn strokes(&self) -> Vec<Vec<Vec2>> {
+        let scale = self.scale();
+        let line_height = CAP_HEIGHT + self.line_spacing;
+        let block_height = self.height() / scale;
+
+        let y_top = match self.v_align {
+            VAlign::Top => 0.0,
+            VAlign::Center => block_height / 2.0,
+            VAlign::Bottom => block_height,
+        };
+
+        let mut strokes = Vec::new();
+        for (line_idx, line) in self.text.lines().enumerate() {
+            let mut x = match self.h_align {
+                HAlign::Left => 0.0,
+                HAlign::Center => -self.line_width(line) / 2.0,
+                HAlign::Right => -self.line_width(line),
+            };
+            let y = y_top - CAP_HEIGHT - line_idx as f32 * line_height;
+
+            let chars = line.chars().collect::<Vec<_>>();
+            for (i, c) in chars.iter().enumerate() {
+                if let Some(glyph) = glyph(*c).or_else(|| glyph('?')) {
+                    strokes.extend(glyph.strokes.iter().map(|stroke| {
+                        stroke
+                            .iter()
+                            .map(|(gx, gy)| Vec2::new(x + gx, y + gy) * scale)
+                            .collect()
+                    }));
+                }
+                x += self.advance(*c, chars.get(i + 1).copied());
+            }
+        }
+
+        strokes
+    }
+
+    /// Returns the laid out text as segments, suitable for [`crate::line_sprite::LineSpriteBundleBuilder::add_segments`].
+    pub fn segments(&self) -> Vec<(Vec2, Vec2)> {
+        self.strokes()
+            .iter()
+            .flat_map(|stroke| stroke.windows(2).map(|w| (w[0], w[1])))
+            .collect()
+    }
+
+    /// Returns the laid out text as paths, suitable for direct inclusion in a `vsvg::Document`.
+    pub fn paths(&self) -> Vec<vsvg::Path> {
+        self.strokes()
+            .iter()
+            .map(|stroke| {
+                vsvg::Path::from_segments(
+                    stroke
+                        .windows(2)
+                        .map(|w| (vsvg::Point::from(w[0]), vsvg::Point::from(w[1]))),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extent(strokes: &[Vec<Vec2>], axis: fn(&Vec2) -> f32) -> (f32, f32) {
+        strokes
+            .iter()
+            .flatten()
+            .map(axis)
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+                (min.min(v), max.max(v))
+            })
+    }
+
+    fn x_extent(strokes: &[Vec<Vec2>]) -> (f32, f32) {
+        extent(strokes, |pt| pt.x)
+    }
+
+    #[test]
+    fn advance_includes_spacing_and_kerning() {
+        let text = StrokeText::new("");
+
+        assert_eq!(text.advance('L', Some('U')), 6.0);
+        assert_eq!(text.advance('A', Some('V')), 5.0);
+        assert_eq!(text.advance('A', None), 6.0);
+        assert_eq!(text.line_width("LU"), 10.0);
+        assert_eq!(text.line_width("AV"), 9.0);
+        assert_eq!(text.line_width(""), 0.0);
+    }
+
+    #[test]
+    fn unknown_characters_use_the_question_mark() {
+        let text = StrokeText::new("");
+        assert_eq!(text.advance('\u{2603}', None), text.advance('?', None),);
+    }
+
+    #[test]
+    fn horizontal_alignment() {
+        // "LU" is 10 glyph units wide, i.e. 20 world units at size 12
+        let layout = |h_align| {
+            x_extent(
+                &StrokeText::new("LU")
+                    .size(12.0)
+                    .align(h_align, VAlign::Bottom)
+                    .strokes(),
+            )
+        };
+
+        assert_eq!(layout(HAlign::Left), (0.0, 20.0));
+        assert_eq!(layout(HAlign::Center), (-10.0, 10.0));
+        assert_eq!(layout(HAlign::Right), (-20.0, 0.0));
+    }
+
+    #[test]
+    fn vertical_alignment_and_lines() {
+        // two 6 units high lines, 4 units apart
+        assert_eq!(StrokeText::new("L\nL").size(6.0).height(), 16.0);
+
+        let y_extent = |v_align| {
+            let strokes = StrokeText::new("L\nL")
+                .size(6.0)
+                .align(HAlign::Left, v_align)
+                .strokes();
+            extent(&strokes, |pt| pt.y)
+        };
+
+        assert_eq!(y_extent(VAlign::Bottom), (0.0, 16.0));
+        assert_eq!(y_extent(VAlign::Top), (-16.0, 0.0));
+        assert_eq!(y_extent(VAlign::Center), (-8.0, 8.0));
+    }
+
+    #[test]
+    fn lines_are_aligned_independently() {
+        let strokes = StrokeText::new("L\nLUL")
+            .size(6.0)
+            .align(HAlign::Center, VAlign::Top)
+            .strokes();
+
+        // the first line's strokes come first, the "L" has two points on each side
+        let (first, second) = strokes.split_at(1);
+        assert_eq!(x_extent(first), (-2.0, 2.0));
+        assert_eq!(x_extent(second), (-8.0, 8.0));
+    }
+
+    #[test]
+    fn spacing() {
+        let text = StrokeText::new("LU\nLU").size(6.0);
+        assert_eq!(x_extent(&text.strokes()), (0.0, 10.0));
+        assert_eq!(text.height(), 16.0);
+
+        let text = text.letter_spacing(0.0).line_spacing(1.0);
+        assert_eq!(x_extent(&text.strokes()), (0.0, 8.0));
+        assert_eq!(text.height(), 13.0);
+    }
+
+    #[test]
+    fn one_path_per_stroke() {
+        let text = StrokeText::new("AB\nC");
+        assert_eq!(text.paths().len(), text.strokes().len());
+    }
+}
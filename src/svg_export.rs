@@ -1,7 +1,8 @@
+use crate::actions::{Action, ActionState};
+use crate::ghosts::wrap_offsets;
+use crate::line_sprite::Segments;
 use crate::Resolution;
 use bevy::prelude::*;
-use bevy::render::mesh::VertexAttributeValues;
-use bevy::sprite::Mesh2dHandle;
 use vsvg::{DocumentTrait, Transforms};
 
 pub struct SvgExportPlugin;
@@ -9,66 +10,137 @@ pub struct SvgExportPlugin;
 impl Plugin for SvgExportPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(SvgExportSettings::default())
-            .add_systems(Update, (keyboard_system,))
+            .add_systems(Update, (export_input_system,))
             .add_systems(Last, (svg_export_system,));
     }
 }
 
-#[derive(Resource, Debug, Default)]
+/// Marker for sprites that are displayed on screen but must not be exported.
+#[derive(Component)]
+pub struct NoSvgExport;
+
+/// Text sprite, exported as the glyph strokes from [`crate::stroke_font::StrokeText::paths`]
+/// rather than as loose segments, so that each stroke is drawn in one go.
+#[derive(Component)]
+pub struct SvgText(pub Vec<vsvg::Path>);
+
+#[derive(Resource, Debug)]
 pub struct SvgExportSettings {
+    /// Where the SVG is written, unused on the web where it is downloaded instead.
     pub export_path: String,
 
     /// Flag to indicate that the export should be run.
     pub run_export: bool,
 }
 
-fn keyboard_system(
+impl Default for SvgExportSettings {
+    fn default() -> Self {
+        Self {
+            export_path: "/tmp/output.svg".to_owned(),
+            run_export: false,
+        }
+    }
+}
+
+fn export_input_system(
     mut svg_export_settings: ResMut<SvgExportSettings>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<ActionState>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::E) {
+    if actions.just_pressed(Action::Export) {
         svg_export_settings.run_export = true;
     }
 }
 
-fn svg_export_system(
-    meshes: Res<Assets<Mesh>>,
+/// Whether an entity is displayed, following the same rules as the renderer.
+///
+/// The renderer's `ComputedVisibility` is not available when running headless.
+fn is_visible(entity: Entity, q_visibility: &Query<(&Visibility, Option<&Parent>)>) -> bool {
+    let mut entity = entity;
+    loop {
+        let Ok((visibility, parent)) = q_visibility.get(entity) else {
+            return true;
+        };
+
+        match (visibility, parent) {
+            (Visibility::Hidden, _) => return false,
+            (Visibility::Inherited, Some(parent)) => entity = parent.get(),
+            _ => return true,
+        }
+    }
+}
+
+/// Exports the line sprites from their segments, so it works without the render stack. Text
+/// sprites are exported from their [`SvgText`] paths instead.
+///
+/// Sprites hanging over the playfield's edges are also exported on the opposite side, like they
+/// are displayed.
+pub fn svg_export_system(
     resolution: Res<Resolution>,
     mut svg_export_settings: ResMut<SvgExportSettings>,
-    query: Query<(&GlobalTransform, &ComputedVisibility, &Mesh2dHandle)>,
+    query: Query<(Entity, &GlobalTransform, &Segments, Option<&SvgText>), Without<NoSvgExport>>,
+    q_visibility: Query<(&Visibility, Option<&Parent>)>,
 ) {
     if svg_export_settings.run_export {
         svg_export_settings.run_export = false;
 
         let mut doc = vsvg::Document::default();
         doc.metadata_mut().page_size = Some(resolution.as_page_size());
+        let playfield = Vec2::new(resolution.width, resolution.height);
 
-        for (transform, visibility, Mesh2dHandle(mesh_handle)) in query.iter() {
-            if !visibility.is_visible() {
+        for (entity, transform, Segments(segments), text) in query.iter() {
+            if segments.is_empty() || !is_visible(entity, &q_visibility) {
                 continue;
             }
 
-            let Some(mesh) = meshes.get(mesh_handle) else {
-                continue;
-            };
-
-            let Some(vertex_attrib) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else {
-                continue;
-            };
-
-            let VertexAttributeValues::Float32x3(vertex_data) = &vertex_attrib else {
-                continue;
-            };
-
             let affine = transform.affine();
-            let vertex_data = vertex_data.chunks(2).map(|vs| {
-                (
-                    vsvg::Point::from(affine.transform_point3(Vec3::from(vs[0])).truncate()),
-                    vsvg::Point::from(affine.transform_point3(Vec3::from(vs[1])).truncate()),
-                )
-            });
-
-            doc.push_path(1, vsvg::Path::from_segments(vertex_data));
+            let segments = segments
+                .iter()
+                .map(|(a, b)| {
+                    (
+                        affine.transform_point3(a.extend(0.0)).truncate(),
+                        affine.transform_point3(b.extend(0.0)).truncate(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let (min, max) = segments.iter().fold(
+                (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+                |(min, max), (a, b)| (min.min(a.min(*b)), max.max(a.max(*b))),
+            );
+            let offsets = std::iter::once(Vec2::ZERO).chain(
+                wrap_offsets(min, max, playfield)
+                    .into_iter()
+                    .map(|offset| offset.as_vec2() * playfield),
+            );
+
+            for offset in offsets {
+                if let Some(SvgText(paths)) = text {
+                    let translation = affine.translation.truncate() + offset;
+                    let affine = kurbo::Affine::new([
+                        affine.x_axis.x as f64,
+                        affine.x_axis.y as f64,
+                        affine.y_axis.x as f64,
+                        affine.y_axis.y as f64,
+                        translation.x as f64,
+                        translation.y as f64,
+                    ]);
+                    for path in paths {
+                        let mut path = path.clone();
+                        path.transform(&affine);
+                        doc.push_path(1, path);
+                    }
+                } else {
+                    doc.push_path(
+                        1,
+                        vsvg::Path::from_segments(segments.iter().map(|(a, b)| {
+                            (
+                                vsvg::Point::from(*a + offset),
+                                vsvg::Point::from(*b + offset),
+                            )
+                        })),
+                    );
+                }
+            }
         }
 
         // convert to SVG coordinate system (y-axis down, origin top-left)
@@ -85,7 +157,9 @@ fn svg_export_system(
 
         #[cfg(not(target_arch = "wasm32"))]
         {
-            let file = std::io::BufWriter::new(std::fs::File::create("/tmp/output.svg").unwrap());
+            let file = std::io::BufWriter::new(
+                std::fs::File::create(&svg_export_settings.export_path).unwrap(),
+            );
             doc.to_svg(file).unwrap();
         }
 
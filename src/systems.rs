@@ -1,39 +1,135 @@
-use crate::asteroids::AsteroidMakerRegistry;
-use crate::components::{Asteroid, Flame, LifeTime, Missile, RotationSpeed, Ship, Speed, Thruster};
-use crate::events::{AsteroidKillEvent, AsteroidSpawnEvent};
-use crate::line_sprite::{LineMaterial, LineSpriteBundleBuilder, Shape};
+use crate::actions::{Action, ActionState};
+use crate::asteroids::{fracture, AsteroidMakerRegistry};
+use crate::collisions::Broadphase;
+use crate::components::{
+    Asteroid, Debris, Flame, Hostile, Hyperspace, LifeTime, Missile, PierceCooldown, Player,
+    PowerUpKind, PowerUps, PreviousTransform, RotationSpeed, Saucer, ShieldRing, Ship, Speed,
+    Thruster, Weapon,
+};
+use crate::events::{
+    AsteroidGeometry, AsteroidKillEvent, AsteroidSpawnEvent, SaucerKillEvent, ShipKillEvent,
+    WaveClearedEvent, WaveStartEvent,
+};
+use crate::line_sprite::{LineMaterial, LineSpriteBundleBuilder, Segments, Shape};
+use crate::resources::{
+    ActionBuffer, AsteroidSplitting, GameMode, GameRng, Lives, Score, ShipRespawn, Wave,
+    WaveDirector,
+};
 use crate::{Resolution, TIME_STEP};
+use bevy::ecs::query::Has;
 use bevy::prelude::*;
-use geo::Intersects;
 use rand::Rng;
+use std::collections::HashSet;
 use std::time::Duration;
 
+const SHIELD_RING_SIDES: usize = 12;
+const SHIELD_RING_RADIUS: f32 = 20.0;
+
+pub fn spawn_ship(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<LineMaterial>>,
+    player: Player,
+    position: Vec2,
+) {
+    let parent = commands
+        .spawn((
+            Ship::default(),
+            player,
+            Speed::default(),
+            Thruster::default(),
+            Weapon::default(),
+            PowerUps::default(),
+            LineSpriteBundleBuilder::new(Shape::from_vertices(
+                [
+                    Vec2::new(-10.0, -5.0),
+                    Vec2::new(10.0, -5.0),
+                    Vec2::new(0.0, 15.0),
+                ],
+                true,
+            ))
+            .transform(Transform::from_translation(position.extend(0.0)))
+            .build(meshes, materials),
+        ))
+        .id();
+
+    let child = commands
+        .spawn((
+            Flame,
+            LineSpriteBundleBuilder::new(Shape::from_vertices(
+                [
+                    Vec2::new(-5.0, -8.0),
+                    Vec2::new(5.0, -8.0),
+                    Vec2::new(0.0, -13.0),
+                ],
+                true,
+            ))
+            .build(meshes, materials),
+        ))
+        .id();
+
+    let shield = commands
+        .spawn((
+            ShieldRing,
+            LineSpriteBundleBuilder::new(Shape::from_vertices(
+                (0..SHIELD_RING_SIDES).map(|i| {
+                    Vec2::from_angle(i as f32 / SHIELD_RING_SIDES as f32 * std::f32::consts::TAU)
+                        * SHIELD_RING_RADIUS
+                }),
+                true,
+            ))
+            .build(meshes, materials),
+        ))
+        .insert(Visibility::Hidden)
+        .id();
+
+    commands.entity(parent).push_children(&[child, shield]);
+}
+
+#[allow(clippy::type_complexity)]
 pub fn ship_motion_system(
-    mut q_parent: Query<(&mut Speed, &mut Transform, &Thruster, &Children, &Ship)>,
-    mut q_child: Query<&mut Visibility, With<Flame>>,
+    mut q_parent: Query<(
+        &mut Speed,
+        &mut Transform,
+        &Thruster,
+        &Children,
+        &Ship,
+        &PowerUps,
+    )>,
+    mut q_flame: Query<&mut Visibility, (With<Flame>, Without<ShieldRing>)>,
+    mut q_shield: Query<&mut Visibility, (With<ShieldRing>, Without<Flame>)>,
 ) {
-    let (mut speed, mut transform, thruster, children, ship) = q_parent.single_mut();
-    let movement_direction = transform.rotation * Vec3::Y;
+    for (mut speed, mut transform, thruster, children, ship, power_ups) in q_parent.iter_mut() {
+        let movement_direction = transform.rotation * Vec3::Y;
 
-    if thruster.active {
-        speed.0 += movement_direction.truncate().normalize() * ship.thrust_accel * TIME_STEP;
-        speed.0 = speed.0.clamp_length_max(ship.max_speed);
-    }
+        if thruster.active {
+            speed.0 += movement_direction.truncate().normalize() * ship.thrust_accel * TIME_STEP;
+            speed.0 = speed.0.clamp_length_max(ship.max_speed);
+        }
 
-    // always decelerate a bit
-    speed.0 = speed
-        .0
-        .clamp_length_max(speed.0.length() + ship.idle_accel * TIME_STEP);
+        // always decelerate a bit
+        speed.0 = speed
+            .0
+            .clamp_length_max(speed.0.length() + ship.idle_accel * TIME_STEP);
 
-    transform.translation += speed.0.extend(0.0) * TIME_STEP;
+        transform.translation += speed.0.extend(0.0) * TIME_STEP;
 
-    for child in children.iter() {
-        let mut visibility = q_child.get_mut(*child).unwrap();
-        *visibility = if thruster.active {
-            Visibility::Visible
-        } else {
-            Visibility::Hidden
-        };
+        for child in children.iter() {
+            if let Ok(mut visibility) = q_flame.get_mut(*child) {
+                *visibility = if thruster.active {
+                    Visibility::Visible
+                } else {
+                    Visibility::Hidden
+                };
+            }
+            if let Ok(mut visibility) = q_shield.get_mut(*child) {
+                *visibility = if power_ups.is_active(PowerUpKind::Shield) {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
     }
 }
 
@@ -61,52 +157,144 @@ pub fn life_time_system(mut commands: Commands, mut query: Query<(Entity, &mut L
     }
 }
 
+/// Angle between the missiles of a spread shot.
+const SPREAD_SHOT_ANGLE: f32 = 0.2;
+
+/// Buffers the ship actions until the next fixed step.
+pub fn action_buffer_system(actions: Res<ActionState>, mut buffer: ResMut<ActionBuffer>) {
+    for player in (0..Player::MAX).map(Player) {
+        for action in ActionBuffer::ACTIONS {
+            if actions.player_just_pressed(player, action) {
+                buffer.just_pressed.insert((player, action));
+            }
+            if actions.player_pressed(player, action) {
+                buffer.pressed.insert((player, action));
+            } else {
+                buffer.pressed.remove(&(player, action));
+            }
+        }
+        buffer.set_rotation(player, actions.rotation(player));
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn spawn_missiles_system(
-    keyboard_input: Res<Input<KeyCode>>,
+    mut buffer: ResMut<ActionBuffer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<LineMaterial>>,
     mut commands: Commands,
-    q_ship: Query<(&Speed, &Transform), With<Ship>>,
+    mut q_ship: Query<
+        (&Player, &Speed, &Transform, &mut Weapon, &PowerUps),
+        (With<Ship>, Without<Hyperspace>),
+    >,
+    q_missile: Query<&Missile>,
 ) {
-    let (Speed(ship_speed), ship_transform) = q_ship.single();
+    let just_pressed = buffer.take_just_pressed(Action::Fire);
 
-    let heading_vec = (ship_transform.rotation * Vec3::Y).truncate();
-    let heading_speed = ship_speed.dot(heading_vec);
+    for (player, Speed(ship_speed), ship_transform, mut weapon, power_ups) in q_ship.iter_mut() {
+        let rapid_fire = power_ups.is_active(PowerUpKind::RapidFire);
+        let (cooldown, max_missiles) = if rapid_fire {
+            (weapon.cooldown * 0.5, weapon.max_missiles * 2)
+        } else {
+            (weapon.cooldown, weapon.max_missiles)
+        };
 
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        let missile = Missile::default();
-        let speed = missile.speed;
-        let time_to_live = missile.time_to_live;
-        let momentum_transfer = missile.momentum_transfer;
+        weapon.reload.tick(Duration::from_secs_f32(TIME_STEP));
 
-        let mut transform = *ship_transform;
-        transform.translation += (heading_vec * Ship::MISSILE_SPAWN_OFFSET).extend(0.0);
+        let trigger = just_pressed.contains(player)
+            || ((weapon.autofire || rapid_fire) && buffer.pressed(*player, Action::Fire));
+        if !trigger || !weapon.reload.finished() {
+            continue;
+        }
 
-        let shape = Shape::from_vertices([Vec2::new(0.0, 0.0), Vec2::new(0.0, 4.0)], false);
-        commands.spawn((
+        let angles: &[f32] = if power_ups.is_active(PowerUpKind::SpreadShot) {
+            &[-SPREAD_SHOT_ANGLE, 0.0, SPREAD_SHOT_ANGLE]
+        } else {
+            &[0.0]
+        };
+
+        // each missile of a spread shot counts against the cap, and the whole volley must fit
+        let missile_count = q_missile
+            .iter()
+            .filter(|missile| missile.owner == Some(*player))
+            .count();
+        if missile_count + angles.len() > max_missiles {
+            continue;
+        }
+
+        weapon
+            .reload
+            .set_duration(Duration::from_secs_f32(cooldown));
+        weapon.reload.reset();
+
+        let heading_vec = (ship_transform.rotation * Vec3::Y).truncate();
+        let heading_speed = ship_speed.dot(heading_vec);
+
+        let mut missile = Missile {
+            piercing: power_ups.is_active(PowerUpKind::Piercing),
+            owner: Some(*player),
+            ..default()
+        };
+        if power_ups.is_active(PowerUpKind::LongRange) {
+            missile.time_to_live *= 2.0;
+        }
+
+        for angle in angles {
+            let direction = Vec2::from_angle(*angle).rotate(heading_vec.normalize());
+            let speed = direction * (heading_speed * missile.momentum_transfer + missile.speed);
+
+            let mut transform = *ship_transform;
+            transform.rotate_z(*angle);
+            transform.translation += (direction * Ship::MISSILE_SPAWN_OFFSET).extend(0.0);
+
+            spawn_missile(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                missile.clone(),
+                transform,
+                speed,
+            );
+        }
+    }
+}
+
+/// Spawns a missile flying at `speed`, whose transform should be aligned with `speed`.
+pub fn spawn_missile(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<LineMaterial>>,
+    missile: Missile,
+    transform: Transform,
+    speed: Vec2,
+) -> Entity {
+    let time_to_live = missile.time_to_live;
+    let shape = Shape::from_vertices([Vec2::new(0.0, 0.0), Vec2::new(0.0, 4.0)], false);
+
+    commands
+        .spawn((
             missile,
-            Speed(heading_vec.normalize() * (heading_speed * momentum_transfer + speed)),
+            Speed(speed),
+            PreviousTransform(transform),
             LifeTime(Timer::from_seconds(time_to_live, TimerMode::Once)),
             LineSpriteBundleBuilder::new(shape)
                 .transform(transform)
-                .build(&mut meshes, &mut materials),
-        ));
-    }
+                .build(meshes, materials),
+        ))
+        .id()
 }
 
-pub fn keyboard_input_system(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<(&mut Transform, &mut Thruster, &Ship)>,
+pub fn ship_input_system(
+    buffer: Res<ActionBuffer>,
+    mut query: Query<(&mut Transform, &mut Thruster, &Ship, &Player), Without<Hyperspace>>,
 ) {
-    let (mut transform, mut thruster, ship) = query.single_mut();
-    if keyboard_input.pressed(KeyCode::Left) {
-        transform.rotate(Quat::from_rotation_z(ship.rot_speed * TIME_STEP));
-    }
-    if keyboard_input.pressed(KeyCode::Right) {
-        transform.rotate(Quat::from_rotation_z(-ship.rot_speed * TIME_STEP));
-    }
+    for (mut transform, mut thruster, ship, player) in query.iter_mut() {
+        transform.rotate(Quat::from_rotation_z(
+            buffer.rotation(*player) * ship.rot_speed * TIME_STEP,
+        ));
 
-    thruster.active = keyboard_input.pressed(KeyCode::Up);
+        thruster.active = buffer.pressed(*player, Action::Thrust);
+    }
 }
 
 pub fn wrap_positions(resolution: Res<Resolution>, mut query: Query<&mut Transform>) {
@@ -124,18 +312,84 @@ pub fn wrap_positions(resolution: Res<Resolution>, mut query: Query<&mut Transfo
     }
 }
 
-/// for the initial asteroid spawn
-pub fn spawn_asteroids_system(
-    mut events: ResMut<Events<AsteroidSpawnEvent>>,
-    query: Query<&Asteroid>,
+/// Spawns a new wave of asteroids, after a short delay, whenever the playfield is cleared.
+#[allow(clippy::too_many_arguments)]
+pub fn wave_director_system(
+    mut spawn_events: ResMut<Events<AsteroidSpawnEvent>>,
+    mut start_sender: EventWriter<WaveStartEvent>,
+    mut cleared_sender: EventWriter<WaveClearedEvent>,
+    mut wave: ResMut<Wave>,
+    mut director: ResMut<WaveDirector>,
+    mut game_rng: ResMut<GameRng>,
+    resolution: Res<Resolution>,
+    q_asteroid: Query<(), With<Asteroid>>,
+    q_ship: Query<&Transform, With<Ship>>,
 ) {
-    if query.is_empty() && events.is_empty() {
-        events.send(AsteroidSpawnEvent {
-            category: 3,
-            start_position: None,
-            start_speed: None,
+    if !q_asteroid.is_empty() || !spawn_events.is_empty() {
+        return;
+    }
+
+    if !director.between_waves {
+        director.between_waves = true;
+        director.delay.reset();
+
+        if wave.0 > 0 {
+            cleared_sender.send(WaveClearedEvent { wave: wave.0 });
+        }
+    }
+
+    director.delay.tick(Duration::from_secs_f32(TIME_STEP));
+    if !director.delay.finished() {
+        return;
+    }
+
+    director.between_waves = false;
+    wave.0 += 1;
+
+    let rng = &mut game_rng.rng;
+    let ship_positions = q_ship
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .collect::<Vec<_>>();
+    let speed_range = director.speed_range(wave.0);
+
+    for category in director.categories(wave.0) {
+        // settle for the candidate farthest from the ships if the playfield is too small to
+        // honour the safe radius
+        let mut start_position = None;
+        let mut best_clearance = f32::NEG_INFINITY;
+        for _ in 0..100 {
+            let pos = Vec2::new(
+                rng.gen_range(-resolution.width / 2.0..resolution.width / 2.0),
+                rng.gen_range(-resolution.height / 2.0..resolution.height / 2.0),
+            );
+            let clearance = ship_positions
+                .iter()
+                .map(|ship_pos| pos.distance(*ship_pos))
+                .fold(f32::INFINITY, f32::min);
+
+            if clearance > best_clearance {
+                best_clearance = clearance;
+                start_position = Some(pos);
+            }
+            if clearance > director.safe_radius {
+                break;
+            }
+        }
+
+        let start_speed = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU))
+            * rng.gen_range(speed_range.clone());
+
+        spawn_events.send(AsteroidSpawnEvent {
+            category,
+            start_position,
+            start_speed: Some(start_speed),
+            start_rotation_speed: None,
+            geometry: None,
         });
     }
+
+    start_sender.send(WaveStartEvent { wave: wave.0 });
 }
 
 pub fn asteroid_birth_system(
@@ -143,15 +397,13 @@ pub fn asteroid_birth_system(
     mut materials: ResMut<Assets<LineMaterial>>,
     asteroid_maker: Res<AsteroidMakerRegistry>,
     mut commands: Commands,
+    mut game_rng: ResMut<GameRng>,
     resolution: Res<Resolution>,
     mut spawn_events: ResMut<Events<AsteroidSpawnEvent>>,
 ) {
-    for e in spawn_events.drain() {
-        let rng = &mut rand::thread_rng();
-        let size = 10.0 * e.category as f32 + rng.gen_range(-2.0..2.0);
-
-        let asteroid_builder = asteroid_maker.get_random();
+    let rng = &mut game_rng.rng;
 
+    for e in spawn_events.drain() {
         let position = e.start_position.unwrap_or_else(|| {
             Vec2::new(
                 rng.gen_range(-resolution.width / 2.0..resolution.width / 2.0),
@@ -161,77 +413,822 @@ pub fn asteroid_birth_system(
 
         let speed = e
             .start_speed
-            .map(|v| v + Vec2::new(rng.gen_range(-20.0..20.0), rng.gen_range(-20.0..20.0)))
             .unwrap_or_else(|| Vec2::new(rng.gen_range(-50.0..50.0), rng.gen_range(-50.0..50.0)));
 
-        let (shape, extra_seg) = asteroid_builder.shape_and_segments(e.category);
+        let (shape, extra_seg, transform) = match e.geometry {
+            Some(geometry) => (
+                geometry.shape,
+                geometry.segments,
+                Transform::from_translation(position.extend(0.0))
+                    .with_rotation(geometry.rotation)
+                    .with_scale(geometry.scale),
+            ),
+            None => {
+                let size = 10.0 * e.category as f32 + rng.gen_range(-2.0..2.0);
+                let (shape, extra_seg) = asteroid_maker
+                    .get_random(rng)
+                    .shape_and_segments(e.category, rng);
+                (
+                    shape,
+                    extra_seg,
+                    Transform::from_translation(position.extend(0.0))
+                        .with_scale(Vec3::new(size, size, 1.0)),
+                )
+            }
+        };
+        let rotation_speed = e
+            .start_rotation_speed
+            .unwrap_or_else(|| rng.gen_range(-1.0..1.0));
 
         commands.spawn((
             Asteroid {
                 category: e.category,
             },
             Speed(speed),
-            RotationSpeed(rng.gen_range(-1.0..1.0)),
+            RotationSpeed(rotation_speed),
             LineSpriteBundleBuilder::new(shape)
                 .add_segments(extra_seg)
-                .transform(
-                    Transform::from_translation(position.extend(0.0))
-                        .with_scale(Vec3::new(size, size, 1.0)),
-                )
+                .transform(transform)
                 .build(&mut meshes, &mut materials),
         ));
     }
 }
 
+/// Time during which a piercing missile ignores asteroids after a hit.
+const PIERCE_COOLDOWN: f32 = 0.15;
+
+/// Number of smaller asteroids a destroyed asteroid breaks into.
+const FRAGMENT_COUNT: usize = 3;
+/// Half angle of the fan in which the fragments fly away from the impact, in radians.
+const FRAGMENT_FAN_ANGLE: f32 = 0.9;
+/// Half angle of the fan of fracture lines cutting across a fractured asteroid, in radians.
+const FRACTURE_FAN_ANGLE: f32 = 0.35;
+/// Range of the fragments' speed relative to the parent's center of mass.
+const FRAGMENT_SPREAD_SPEED: std::ops::Range<f32> = 30.0..70.0;
+/// Mass of a missile relative to a category 1 asteroid, the asteroids' mass growing with the
+/// square of their category.
+const MISSILE_MASS_RATIO: f32 = 0.2;
+
+/// State of an asteroid at the moment a missile breaks it apart.
+struct Breakup {
+    transform: Transform,
+    speed: Vec2,
+    rotation_speed: f32,
+    category: u8,
+    impact_point: Vec2,
+    /// Direction from the impact point towards the asteroid's center.
+    impact_direction: Vec2,
+    /// Speed gained by the whole asteroid from absorbing the missile.
+    kick: Vec2,
+}
+
+impl Breakup {
+    fn new(
+        transform: &Transform,
+        speed: Vec2,
+        rotation_speed: f32,
+        category: u8,
+        impact_point: Vec2,
+        missile_speed: Vec2,
+    ) -> Self {
+        let impact_direction = (transform.translation.truncate() - impact_point)
+            .try_normalize()
+            .or_else(|| missile_speed.try_normalize())
+            .unwrap_or(Vec2::Y);
+
+        // perfectly inelastic collision between the missile and the asteroid
+        let mass = (category as f32).powi(2);
+        let kick = (missile_speed - speed) * MISSILE_MASS_RATIO / (mass + MISSILE_MASS_RATIO);
+
+        Self {
+            transform: *transform,
+            speed,
+            rotation_speed,
+            category,
+            impact_point,
+            impact_direction,
+            kick,
+        }
+    }
+
+    fn center(&self) -> Vec2 {
+        self.transform.translation.truncate()
+    }
+
+    /// Speed and rotation speed of the fragments, given their offset from the asteroid's center,
+    /// their spread speed and their mass.
+    ///
+    /// The spread doesn't move the center of mass, which only picks up the missile's momentum.
+    /// Each fragment also keeps the velocity it had as a part of the spinning asteroid, and
+    /// roughly its rotation speed.
+    fn fragment_motion(
+        &self,
+        fragments: &[(Vec2, Vec2, f32)],
+        rng: &mut impl Rng,
+    ) -> Vec<(Vec2, f32)> {
+        let total_mass = fragments.iter().map(|(_, _, mass)| mass).sum::<f32>();
+        let mean_spread = fragments
+            .iter()
+            .map(|(_, spread, mass)| *spread * *mass)
+            .sum::<Vec2>()
+            / total_mass;
+
+        fragments
+            .iter()
+            .map(|(offset, spread, _)| {
+                let speed = self.speed + self.kick + *spread - mean_spread
+                    + self.rotation_speed * offset.perp();
+                (speed, self.rotation_speed + rng.gen_range(-0.3..0.3))
+            })
+            .collect()
+    }
+
+    /// Replaces the asteroid with new random ones, flying apart in a fan opening away from the
+    /// impact.
+    fn respawn(&self, rng: &mut impl Rng) -> Vec<AsteroidSpawnEvent> {
+        let directions = (0..FRAGMENT_COUNT)
+            .map(|idx| {
+                let fan_position = idx as f32 / (FRAGMENT_COUNT - 1) as f32 * 2.0 - 1.0;
+                let angle = fan_position * FRAGMENT_FAN_ANGLE + rng.gen_range(-0.2..0.2);
+                Vec2::from_angle(angle).rotate(self.impact_direction)
+            })
+            .collect::<Vec<_>>();
+
+        // fragments start halfway to the asteroid's edge, so they don't all overlap
+        let offsets = directions
+            .iter()
+            .map(|direction| *direction * self.transform.scale.x * 0.5)
+            .collect::<Vec<_>>();
+        let mean_offset = offsets.iter().sum::<Vec2>() / FRAGMENT_COUNT as f32;
+
+        let fragments = directions
+            .iter()
+            .zip(offsets)
+            .map(|(direction, offset)| {
+                (
+                    offset - mean_offset,
+                    *direction * rng.gen_range(FRAGMENT_SPREAD_SPEED),
+                    1.0,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        fragments
+            .iter()
+            .zip(self.fragment_motion(&fragments, rng))
+            .map(
+                |((offset, _, _), (speed, rotation_speed))| AsteroidSpawnEvent {
+                    category: self.category - 1,
+                    start_position: Some(self.center() + *offset),
+                    start_speed: Some(speed),
+                    start_rotation_speed: Some(rotation_speed),
+                    geometry: None,
+                },
+            )
+            .collect()
+    }
+
+    /// Cuts the asteroid along fracture lines through the impact point, the pieces flying away
+    /// from it.
+    ///
+    /// Returns nothing if the asteroid couldn't be cut in at least two large enough pieces.
+    fn fracture(
+        &self,
+        shape: &Shape,
+        segments: &[(Vec2, Vec2)],
+        min_area_ratio: f32,
+        rng: &mut impl Rng,
+    ) -> Vec<AsteroidSpawnEvent> {
+        let local_impact_point = self
+            .transform
+            .compute_affine()
+            .inverse()
+            .transform_point3(self.impact_point.extend(0.0))
+            .truncate();
+        let local_impact_direction =
+            (self.transform.rotation.inverse() * self.impact_direction.extend(0.0)).truncate();
+        let decorations = segments.get(shape.segments().len()..).unwrap_or_default();
+
+        let pieces = fracture(
+            shape,
+            decorations,
+            local_impact_point,
+            local_impact_direction,
+            FRAGMENT_COUNT,
+            FRACTURE_FAN_ANGLE,
+            rng,
+        )
+        .into_iter()
+        .filter(|piece| piece.area_ratio >= min_area_ratio)
+        .collect::<Vec<_>>();
+        if pieces.len() < 2 {
+            return vec![];
+        }
+
+        let fragments = pieces
+            .iter()
+            .map(|piece| {
+                let position = self
+                    .transform
+                    .transform_point(piece.center.extend(0.0))
+                    .truncate();
+                let direction = (position - self.impact_point)
+                    .try_normalize()
+                    .unwrap_or(self.impact_direction);
+                (
+                    position - self.center(),
+                    direction * rng.gen_range(FRAGMENT_SPREAD_SPEED),
+                    piece.area_ratio,
+                )
+            })
+            .collect::<Vec<_>>();
+        let motions = self.fragment_motion(&fragments, rng);
+
+        pieces
+            .into_iter()
+            .zip(fragments)
+            .zip(motions)
+            .map(
+                |((piece, (offset, _, _)), (speed, rotation_speed))| AsteroidSpawnEvent {
+                    category: self.category - 1,
+                    start_position: Some(self.center() + offset),
+                    start_speed: Some(speed),
+                    start_rotation_speed: Some(rotation_speed),
+                    geometry: Some(AsteroidGeometry {
+                        shape: piece.shape,
+                        segments: piece.segments,
+                        rotation: self.transform.rotation,
+                        scale: self.transform.scale,
+                    }),
+                },
+            )
+            .collect()
+    }
+}
+
+/// Destroys the asteroids hit by missiles or rammed by saucers, and splits them into smaller ones.
+///
+/// Only the ships' missiles score, saucers and their shots are mere hazards breaking up the field.
+/// All contacts are gathered first and resolved from the earliest along the missiles' paths, so
+/// that each missile, saucer and asteroid takes part in at most one hit per step.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn asteroid_kill_system(
-    q_asteroid: Query<(Entity, &Transform, &Shape, &Asteroid)>,
-    q_missile: Query<(Entity, &Transform, &Shape), With<Missile>>,
+    q_asteroid: Query<(
+        Entity,
+        &Transform,
+        &Speed,
+        &RotationSpeed,
+        &Shape,
+        &Segments,
+        &Asteroid,
+    )>,
+    q_missile: Query<(&Speed, &Missile), Without<PierceCooldown>>,
+    q_saucer: Query<&Speed, With<Saucer>>,
+    broadphase: Res<Broadphase>,
+    splitting: Res<AsteroidSplitting>,
     mut commands: Commands,
+    mut game_rng: ResMut<GameRng>,
     mut kill_sender: EventWriter<AsteroidKillEvent>,
     mut spawn_sender: EventWriter<AsteroidSpawnEvent>,
 ) {
-    for (asteroid_entity, asteroid_transform, asteroid_shape, asteroid) in q_asteroid.iter() {
-        let asteroid_geom = asteroid_shape.as_geometry(asteroid_transform);
+    let rng = &mut game_rng.rng;
 
-        for (missile_entity, missile_transform, missile_shape) in q_missile.iter() {
-            let missile_geom = missile_shape.as_geometry(missile_transform);
-
-            let collision = if let (Some(asteroid_geom), Some(missile_geom)) =
-                (&asteroid_geom, &missile_geom)
+    let mut contacts = vec![];
+    for (asteroid_entity, ..) in q_asteroid.iter() {
+        for killer_entity in broadphase
+            .hits(asteroid_entity)
+            .filter(|entity| q_missile.contains(*entity) || q_saucer.contains(*entity))
+        {
+            // missiles move several times their length per step, the sweep tells where they hit;
+            // saucers aren't swept, they hit from their center right at the start
+            if let Some((impact_point, time)) =
+                broadphase.earliest_hit(killer_entity, asteroid_entity)
             {
-                asteroid_geom.intersects(missile_geom)
+                contacts.push((time, asteroid_entity, killer_entity, impact_point));
+            }
+        }
+    }
+    // earliest contacts first, going by when in the step each missile got there; the sort is
+    // stable, so simultaneous contacts keep a reproducible order
+    contacts.sort_by(|(a, ..), (b, ..)| a.total_cmp(b));
+
+    let mut resolved = HashSet::new();
+    for (_, asteroid_entity, killer_entity, impact_point) in contacts {
+        if resolved.contains(&asteroid_entity) || resolved.contains(&killer_entity) {
+            continue;
+        }
+        resolved.extend([asteroid_entity, killer_entity]);
+
+        let Ok((
+            _,
+            asteroid_transform,
+            asteroid_speed,
+            asteroid_rotation_speed,
+            asteroid_shape,
+            asteroid_segments,
+            asteroid,
+        )) = q_asteroid.get(asteroid_entity)
+        else {
+            continue;
+        };
+
+        let (killer_speed, by) = match (q_missile.get(killer_entity), q_saucer.get(killer_entity)) {
+            (Ok((missile_speed, missile)), _) => {
+                if missile.piercing {
+                    // keep the missile from shattering the fragments it just created
+                    commands
+                        .entity(killer_entity)
+                        .insert(PierceCooldown(Timer::from_seconds(
+                            PIERCE_COOLDOWN,
+                            TimerMode::Once,
+                        )));
+                } else {
+                    commands.entity(killer_entity).despawn();
+                }
+                // enemy missiles have no owner, and score nothing
+                (missile_speed.0, missile.owner)
+            }
+            // the saucer itself is destroyed by `saucer_kill_system`
+            (_, Ok(saucer_speed)) => (saucer_speed.0, None),
+            _ => continue,
+        };
+
+        kill_sender.send(AsteroidKillEvent {
+            id: asteroid_entity,
+            category: asteroid.category,
+            transform: *asteroid_transform,
+            speed: asteroid_speed.0,
+            rotation_speed: asteroid_rotation_speed.0,
+            shape: asteroid_shape.clone(),
+            segments: asteroid_segments.0.clone(),
+            impact_point,
+            killer: killer_entity,
+            by,
+        });
+        commands.entity(asteroid_entity).despawn();
+
+        // spawn new asteroids
+        if asteroid.category > 1 {
+            let breakup = Breakup::new(
+                asteroid_transform,
+                asteroid_speed.0,
+                asteroid_rotation_speed.0,
+                asteroid.category,
+                impact_point,
+                killer_speed,
+            );
+
+            let mut fragments = if splitting.fracture {
+                breakup.fracture(
+                    asteroid_shape,
+                    &asteroid_segments.0,
+                    splitting.min_area_ratio,
+                    rng,
+                )
             } else {
-                false
+                vec![]
             };
+            if fragments.is_empty() {
+                fragments = breakup.respawn(rng);
+            }
+            spawn_sender.send_batch(fragments);
+        }
+    }
+}
 
-            if collision {
-                kill_sender.send(AsteroidKillEvent {
-                    id: asteroid_entity,
-                });
-                commands.entity(asteroid_entity).despawn();
-                commands.entity(missile_entity).despawn();
-
-                // spawn new asteroids
-                if asteroid.category > 1 {
-                    let mut rng = rand::thread_rng();
-                    for _ in 0..3 {
-                        spawn_sender.send(AsteroidSpawnEvent {
-                            category: asteroid.category - 1,
-                            start_position: Some(asteroid_transform.translation.truncate()),
-                            start_speed: Some(Vec2::new(
-                                rng.gen_range(-50.0..50.0),
-                                rng.gen_range(-50.0..50.0),
-                            )),
-                        });
-                    }
-                }
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn ship_kill_system(
+    q_ship: Query<(Entity, &Player, &Transform, &PowerUps), (With<Ship>, Without<Hyperspace>)>,
+    q_hazard: Query<(Entity, Has<Hostile>), Or<(With<Asteroid>, With<Saucer>, With<Hostile>)>>,
+    q_missile: Query<(Entity, &Missile), Without<Hostile>>,
+    broadphase: Res<Broadphase>,
+    mode: Res<GameMode>,
+    mut commands: Commands,
+    mut lives: ResMut<Lives>,
+    mut respawn: ResMut<ShipRespawn>,
+    mut kill_sender: EventWriter<ShipKillEvent>,
+) {
+    for (ship_entity, player, ship_transform, power_ups) in q_ship.iter() {
+        if power_ups.is_active(PowerUpKind::Shield) {
+            continue;
+        }
+
+        let hazard = broadphase
+            .hits(ship_entity)
+            .find_map(|entity| q_hazard.get(entity).ok());
+
+        // in versus mode, the other players' missiles are just as deadly
+        let player_missile = broadphase
+            .hits(ship_entity)
+            .filter(|_| mode.friendly_fire())
+            .filter_map(|entity| q_missile.get(entity).ok())
+            .find(|(_, missile)| missile.owner.is_some_and(|owner| owner != *player));
+
+        let by = if let Some((missile_entity, missile)) = player_missile {
+            commands.entity(missile_entity).despawn();
+            missile.owner
+        } else if let Some((hazard_entity, is_missile)) = hazard {
+            if is_missile {
+                commands.entity(hazard_entity).despawn();
             }
+            None
+        } else {
+            continue;
+        };
+
+        destroy_ship(
+            &mut commands,
+            ship_entity,
+            *player,
+            ship_transform,
+            by,
+            &mut lives,
+            &mut respawn,
+            &mut kill_sender,
+        );
+    }
+}
+
+/// Removes a ship from play and takes away one of its player's lives.
+#[allow(clippy::too_many_arguments)]
+pub fn destroy_ship(
+    commands: &mut Commands,
+    ship_entity: Entity,
+    player: Player,
+    ship_transform: &Transform,
+    by: Option<Player>,
+    lives: &mut ResMut<Lives>,
+    respawn: &mut ResMut<ShipRespawn>,
+    kill_sender: &mut EventWriter<ShipKillEvent>,
+) {
+    kill_sender.send(ShipKillEvent {
+        player,
+        position: ship_transform.translation.truncate(),
+        by,
+    });
+    commands.entity(ship_entity).despawn_recursive();
+
+    lives.lose(player);
+    if let Some(timer) = respawn.timers.get_mut(player.0) {
+        timer.reset();
+    }
+}
+
+/// Brings the ships back once the respawn delay is over and their spawn point is clear of
+/// asteroids.
+#[allow(clippy::too_many_arguments)]
+pub fn ship_respawn_system(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    lives: Res<Lives>,
+    mut respawn: ResMut<ShipRespawn>,
+    q_ship: Query<&Player, With<Ship>>,
+    q_asteroid: Query<&Transform, With<Asteroid>>,
+) {
+    for player in mode.players() {
+        if q_ship.iter().any(|p| *p == player) || lives.get(player) == 0 {
+            continue;
+        }
+
+        let spawn_point = respawn.spawn_point(player);
+        let safe_radius = respawn.safe_radius;
+        let Some(timer) = respawn.timers.get_mut(player.0) else {
+            continue;
+        };
+
+        timer.tick(Duration::from_secs_f32(TIME_STEP));
+        if !timer.finished() {
+            continue;
+        }
+
+        // asteroids are scaled such that their radius is approximately their scale
+        let spawn_area_clear = q_asteroid.iter().all(|transform| {
+            transform.translation.truncate().distance(spawn_point) > safe_radius + transform.scale.x
+        });
+
+        if spawn_area_clear {
+            spawn_ship(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                player,
+                spawn_point,
+            );
+        }
+    }
+}
+
+pub fn score_system(
+    mut score: ResMut<Score>,
+    mut asteroid_receiver: EventReader<AsteroidKillEvent>,
+    mut saucer_receiver: EventReader<SaucerKillEvent>,
+    mut ship_receiver: EventReader<ShipKillEvent>,
+) {
+    for event in asteroid_receiver.iter() {
+        if let Some(player) = event.by {
+            score.add(player, Asteroid::points(event.category));
         }
     }
+
+    for event in saucer_receiver.iter() {
+        if let Some(player) = event.by {
+            score.add(player, Saucer::points(event.size));
+        }
+    }
+
+    for event in ship_receiver.iter() {
+        if let Some(player) = event.by {
+            score.add(player, Ship::POINTS);
+        }
+    }
+}
+
+/// Breaks destroyed asteroids into individual line fragments flying outward.
+pub fn explode_asteroid(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    mut game_rng: ResMut<GameRng>,
+    mut receiver: EventReader<AsteroidKillEvent>,
+) {
+    let rng = &mut game_rng.rng;
+
+    for event in receiver.iter() {
+        spawn_debris(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &event.transform,
+            event.speed,
+            event.rotation_speed,
+            &event.segments,
+            rng,
+        );
+    }
+}
+
+/// Spawns one debris fragment per segment, flying outward from the transform's origin.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_debris(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<LineMaterial>>,
+    transform: &Transform,
+    speed: Vec2,
+    rotation_speed: f32,
+    segments: &[(Vec2, Vec2)],
+    rng: &mut impl Rng,
+) {
+    let center = transform.translation.truncate();
+
+    for (a, b) in segments {
+        let a = transform.transform_point(a.extend(0.0)).truncate();
+        let b = transform.transform_point(b.extend(0.0)).truncate();
+        let mid = (a + b) / 2.0;
+
+        let direction = (mid - center)
+            .try_normalize()
+            .unwrap_or_else(|| Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU)));
+
+        commands.spawn((
+            Debris,
+            Speed(speed + direction * rng.gen_range(20.0..80.0)),
+            RotationSpeed(rotation_speed + rng.gen_range(-3.0..3.0)),
+            LifeTime(Timer::from_seconds(
+                rng.gen_range(0.5..1.5),
+                TimerMode::Once,
+            )),
+            LineSpriteBundleBuilder::new(Shape::from_vertices([a - mid, b - mid], false))
+                .transform(Transform::from_translation(mid.extend(0.0)))
+                .build(meshes, materials),
+        ));
+    }
+}
+
+/// Shrinks debris fragments as their life time runs out.
+pub fn debris_system(mut query: Query<(&LifeTime, &mut Transform), With<Debris>>) {
+    for (life_time, mut transform) in query.iter_mut() {
+        let scale = life_time.0.percent_left();
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
 }
 
-pub fn explode_asteroid(mut receiver: EventReader<AsteroidKillEvent>) {
-    for _event in receiver.iter() {
-        //todo
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collisions::update_broadphase_system;
+    use crate::components::SaucerSize;
+    use bevy::asset::AssetPlugin;
+
+    /// Fires once with `existing` missiles of the ship already on screen, and returns how many
+    /// there are afterwards.
+    fn fire_once(existing: usize, spread_shot: bool) -> usize {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<Mesh>()
+            .add_asset::<LineMaterial>()
+            .init_resource::<ActionBuffer>()
+            .add_systems(Update, spawn_missiles_system);
+
+        let mut power_ups = PowerUps::default();
+        if spread_shot {
+            power_ups.activate(PowerUpKind::SpreadShot, 10.0);
+        }
+        app.world.spawn((
+            Ship::default(),
+            Player(0),
+            Speed(Vec2::ZERO),
+            Transform::default(),
+            Weapon::default(),
+            power_ups,
+        ));
+        for _ in 0..existing {
+            app.world.spawn(Missile {
+                owner: Some(Player(0)),
+                ..default()
+            });
+        }
+
+        app.world
+            .resource_mut::<ActionBuffer>()
+            .just_pressed
+            .insert((Player(0), Action::Fire));
+        app.update();
+
+        app.world
+            .query_filtered::<(), With<Missile>>()
+            .iter(&app.world)
+            .count()
+    }
+
+    /// Runs the asteroid kill system on the given entities, after indexing them.
+    fn kill_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(Resolution {
+            width: 800.0,
+            height: 600.0,
+        })
+        .insert_resource(GameRng::new(Some(0)))
+        .init_resource::<Broadphase>()
+        .init_resource::<AsteroidSplitting>()
+        .init_resource::<Events<AsteroidSpawnEvent>>()
+        .add_event::<AsteroidKillEvent>()
+        .add_systems(
+            Update,
+            (
+                update_broadphase_system,
+                asteroid_kill_system.after(update_broadphase_system),
+            ),
+        );
+        app
+    }
+
+    /// Category 1 asteroid, which doesn't split, as large as the smallest ones.
+    fn spawn_asteroid(app: &mut App, center: Vec2) -> Entity {
+        app.world
+            .spawn((
+                Asteroid { category: 1 },
+                Transform::from_translation(center.extend(0.0)).with_scale(Vec3::splat(8.0)),
+                Speed(Vec2::ZERO),
+                RotationSpeed(0.0),
+                Shape::from_vertices([Vec2::X, Vec2::Y, Vec2::NEG_X, Vec2::NEG_Y], true),
+                Segments::default(),
+            ))
+            .id()
+    }
+
+    /// Missile which moved by `step` during the last fixed step, heading the same way.
+    fn spawn_moved_missile(app: &mut App, previous: Vec2, step: Vec2) -> Entity {
+        let rotation = Quat::from_rotation_arc_2d(Vec2::Y, step.normalize());
+        let previous = Transform::from_translation(previous.extend(0.0)).with_rotation(rotation);
+
+        app.world
+            .spawn((
+                Missile::default(),
+                Speed(step / TIME_STEP),
+                Transform::from_translation((previous.translation.truncate() + step).extend(0.0))
+                    .with_rotation(rotation),
+                PreviousTransform(previous),
+                Shape::from_vertices([Vec2::new(0.0, 0.0), Vec2::new(0.0, 4.0)], false),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn earliest_missile_in_time_wins() {
+        let mut app = kill_app();
+        let asteroid = spawn_asteroid(&mut app, Vec2::ZERO);
+
+        // 5 units per step, its tip reaches the asteroid after 3 units, 60% through the step,
+        // 7 units along its 9 units long path
+        let slow = spawn_moved_missile(&mut app, Vec2::new(7.0, -8.0), Vec2::new(0.0, 5.0));
+        // 15 units per step, its tip reaches the asteroid after 6 units, 40% through the step,
+        // 10 units along its 19 units long path
+        let fast = spawn_moved_missile(&mut app, Vec2::new(-11.0, 7.0), Vec2::new(15.0, 0.0));
+
+        app.update();
+
+        assert!(app.world.get_entity(asteroid).is_none());
+        assert!(app.world.get_entity(fast).is_none());
+        assert!(app.world.get_entity(slow).is_some());
+    }
+
+    #[test]
+    fn earliest_missile_in_time_wins_regardless_of_length() {
+        let mut app = kill_app();
+        let asteroid = spawn_asteroid(&mut app, Vec2::ZERO);
+
+        // 5 units per step, its tip reaches the asteroid halfway through the step, 6.5 units along
+        // its 9 units long path
+        let slow = spawn_moved_missile(&mut app, Vec2::new(1.0, -13.5), Vec2::new(0.0, 5.0));
+        // 15 units per step, its tip reaches the asteroid 60% through the step, 13 units along
+        // its 19 units long path
+        let fast = spawn_moved_missile(&mut app, Vec2::new(-20.0, 1.0), Vec2::new(15.0, 0.0));
+
+        app.update();
+
+        assert!(app.world.get_entity(asteroid).is_none());
+        assert!(app.world.get_entity(slow).is_none());
+        assert!(app.world.get_entity(fast).is_some());
+    }
+
+    #[test]
+    fn missile_hits_the_first_asteroid_on_its_way() {
+        let mut app = kill_app();
+        let second = spawn_asteroid(&mut app, Vec2::new(0.0, 6.0));
+        let first = spawn_asteroid(&mut app, Vec2::ZERO);
+
+        // crossing both asteroids' tips in a single step
+        let missile = spawn_moved_missile(&mut app, Vec2::new(7.0, -7.0), Vec2::new(0.0, 15.0));
+
+        app.update();
+
+        assert!(app.world.get_entity(first).is_none());
+        assert!(app.world.get_entity(missile).is_none());
+        assert!(app.world.get_entity(second).is_some());
+    }
+
+    fn kill_events(app: &App) -> Vec<(Entity, Option<Player>)> {
+        let events = app.world.resource::<Events<AsteroidKillEvent>>();
+        events
+            .get_reader()
+            .iter(events)
+            .map(|event| (event.killer, event.by))
+            .collect()
+    }
+
+    #[test]
+    fn enemy_missiles_break_asteroids_without_scoring() {
+        let mut app = kill_app();
+        let asteroid = spawn_asteroid(&mut app, Vec2::ZERO);
+        let missile = spawn_moved_missile(&mut app, Vec2::new(1.0, -13.5), Vec2::new(0.0, 5.0));
+        app.world.entity_mut(missile).insert(Hostile);
+
+        app.update();
+
+        assert!(app.world.get_entity(asteroid).is_none());
+        assert!(app.world.get_entity(missile).is_none());
+        assert_eq!(kill_events(&app), vec![(missile, None)]);
+    }
+
+    #[test]
+    fn saucers_break_asteroids_they_ram() {
+        let mut app = kill_app();
+        let asteroid = spawn_asteroid(&mut app, Vec2::ZERO);
+        let saucer = app
+            .world
+            .spawn((
+                Saucer::new(SaucerSize::Small),
+                Transform::from_xyz(10.0, 0.0, 0.0).with_scale(Vec3::splat(5.0)),
+                Speed(Vec2::new(-120.0, 0.0)),
+                Shape::from_vertices([Vec2::X, Vec2::Y, Vec2::NEG_X, Vec2::NEG_Y], true),
+            ))
+            .id();
+
+        app.update();
+
+        assert!(app.world.get_entity(asteroid).is_none());
+        // the saucer is destroyed by its own kill system
+        assert!(app.world.get_entity(saucer).is_some());
+        assert_eq!(kill_events(&app), vec![(saucer, None)]);
+    }
+
+    #[test]
+    fn missile_cap() {
+        // the default weapon allows 4 missiles on screen
+        assert_eq!(fire_once(0, false), 1);
+        assert_eq!(fire_once(3, false), 4);
+        assert_eq!(fire_once(4, false), 4);
+    }
+
+    #[test]
+    fn missile_cap_with_spread_shot() {
+        assert_eq!(fire_once(0, true), 3);
+        assert_eq!(fire_once(1, true), 4);
+        // a volley that doesn't fit entirely isn't fired
+        assert_eq!(fire_once(2, true), 2);
+        assert_eq!(fire_once(3, true), 3);
     }
 }
@@ -0,0 +1,222 @@
+use crate::actions::{action_state_system, Action, ActionState};
+use crate::components::Player;
+use crate::line_sprite::{LineMaterial, LineSpriteBundleBuilder, Shape};
+use crate::svg_export::{NoSvgExport, SvgExportSettings};
+use crate::Resolution;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// On-screen buttons for touch screens, shown as soon as the screen is touched.
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchControls>()
+            .add_systems(
+                PreUpdate,
+                (
+                    touch_action_system.after(action_state_system),
+                    touch_export_system.after(InputSystem),
+                ),
+            )
+            .add_systems(Update, (touch_button_layout_system,));
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct TouchControls {
+    /// Set when the first touch is detected, the buttons stay hidden until then.
+    pub enabled: bool,
+}
+
+/// Virtual button triggering `action` while a touch lies within `half_size` of `center`.
+#[derive(Component, Debug)]
+struct TouchButton {
+    action: Action,
+    center: Vec2,
+    half_size: Vec2,
+}
+
+impl TouchButton {
+    fn contains(&self, position: Vec2) -> bool {
+        let offset = (position - self.center).abs();
+        offset.x <= self.half_size.x && offset.y <= self.half_size.y
+    }
+}
+
+const BUTTON_SIZE: f32 = 60.0;
+const BUTTON_MARGIN: f32 = 20.0;
+
+/// Longest press, in seconds, and largest movement, in pixels, still counting as a tap.
+const TAP_DURATION: f32 = 0.25;
+const TAP_SLOP: f32 = 10.0;
+/// Longest delay between the two taps of a double tap, in seconds.
+const DOUBLE_TAP_DELAY: f32 = 0.35;
+
+/// Symbol drawn inside each button, in a unit square centered on the origin.
+fn button_icon(action: Action) -> Vec<Vec2> {
+    match action {
+        Action::RotateLeft => vec![
+            Vec2::new(0.25, -0.3),
+            Vec2::new(-0.25, 0.0),
+            Vec2::new(0.25, 0.3),
+        ],
+        Action::RotateRight => vec![
+            Vec2::new(-0.25, -0.3),
+            Vec2::new(0.25, 0.0),
+            Vec2::new(-0.25, 0.3),
+        ],
+        Action::Thrust => vec![
+            Vec2::new(-0.3, -0.25),
+            Vec2::new(0.0, 0.25),
+            Vec2::new(0.3, -0.25),
+        ],
+        _ => (0..=8)
+            .map(|i| Vec2::from_angle(i as f32 / 8.0 * std::f32::consts::TAU) * 0.2)
+            .collect(),
+    }
+}
+
+/// Rebuilds the buttons along the bottom edge when they first show up or the window is resized.
+fn touch_button_layout_system(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+    mut commands: Commands,
+    resolution: Res<Resolution>,
+    controls: Res<TouchControls>,
+    query: Query<Entity, With<TouchButton>>,
+) {
+    if !controls.enabled || !(controls.is_changed() || resolution.is_changed()) {
+        return;
+    }
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let bottom = -resolution.height / 2.0 + BUTTON_MARGIN + BUTTON_SIZE / 2.0;
+    let left = -resolution.width / 2.0 + BUTTON_MARGIN + BUTTON_SIZE / 2.0;
+    let right = resolution.width / 2.0 - BUTTON_MARGIN - BUTTON_SIZE / 2.0;
+    let step = BUTTON_SIZE + BUTTON_MARGIN;
+
+    let buttons = [
+        (Action::RotateLeft, Vec2::new(left, bottom)),
+        (Action::RotateRight, Vec2::new(left + step, bottom)),
+        (Action::Thrust, Vec2::new(right - step, bottom)),
+        (Action::Fire, Vec2::new(right, bottom)),
+    ];
+
+    let half_size = Vec2::splat(BUTTON_SIZE / 2.0);
+    for (action, center) in buttons {
+        let outline = Shape::from_vertices(
+            [
+                Vec2::new(-half_size.x, -half_size.y),
+                Vec2::new(half_size.x, -half_size.y),
+                Vec2::new(half_size.x, half_size.y),
+                Vec2::new(-half_size.x, half_size.y),
+            ],
+            true,
+        );
+        let icon = button_icon(action)
+            .into_iter()
+            .map(|vertex| vertex * BUTTON_SIZE);
+
+        // the buttons are part of the interface, not of the game
+        commands.spawn((
+            TouchButton {
+                action,
+                center,
+                half_size,
+            },
+            NoSvgExport,
+            LineSpriteBundleBuilder::new(outline)
+                .add_line_string(icon)
+                .transform(Transform::from_translation(center.extend(0.0)))
+                .build(&mut meshes, &mut materials),
+        ));
+    }
+}
+
+/// Converts a touch position from window to world coordinates.
+fn touch_to_world(camera: &Camera, camera_transform: &GlobalTransform, position: Vec2) -> Vec2 {
+    // positions are relative to the viewport, which is letterboxed within the window
+    let viewport_origin = camera
+        .logical_viewport_rect()
+        .map_or(Vec2::ZERO, |rect| rect.min);
+    camera
+        .viewport_to_world_2d(camera_transform, position - viewport_origin)
+        .unwrap_or(position)
+}
+
+/// Presses the actions of the buttons under each finger, so several can be held at once.
+fn touch_action_system(
+    touches: Res<Touches>,
+    mut controls: ResMut<TouchControls>,
+    mut actions: ResMut<ActionState>,
+    q_camera: Query<(&Camera, &GlobalTransform)>,
+    q_button: Query<&TouchButton>,
+) {
+    if !controls.enabled && touches.any_just_pressed() {
+        controls.enabled = true;
+    }
+
+    let Ok((camera, camera_transform)) = q_camera.get_single() else {
+        return;
+    };
+
+    for touch in touches.iter() {
+        let position = touch_to_world(camera, camera_transform, touch.position());
+
+        for button in q_button.iter().filter(|button| button.contains(position)) {
+            // touch controls always drive the first player
+            actions.press(Player(0), button.action, touches.just_pressed(touch.id()));
+        }
+    }
+}
+
+/// Exports the playfield to SVG on a double tap away from the buttons.
+fn touch_export_system(
+    time: Res<Time>,
+    touches: Res<Touches>,
+    mut svg_export_settings: ResMut<SvgExportSettings>,
+    mut press_times: Local<HashMap<u64, f32>>,
+    mut last_tap: Local<Option<f32>>,
+    q_camera: Query<(&Camera, &GlobalTransform)>,
+    q_button: Query<&TouchButton>,
+) {
+    let now = time.elapsed_seconds();
+
+    for touch in touches.iter_just_pressed() {
+        press_times.insert(touch.id(), now);
+    }
+
+    let Ok((camera, camera_transform)) = q_camera.get_single() else {
+        return;
+    };
+
+    for touch in touches.iter_just_released() {
+        let Some(press_time) = press_times.remove(&touch.id()) else {
+            continue;
+        };
+
+        let position = touch_to_world(camera, camera_transform, touch.position());
+        let is_tap = now - press_time <= TAP_DURATION
+            && touch.distance().length() <= TAP_SLOP
+            && !q_button.iter().any(|button| button.contains(position));
+        if !is_tap {
+            continue;
+        }
+
+        if last_tap.is_some_and(|last| now - last <= DOUBLE_TAP_DELAY) {
+            svg_export_settings.run_export = true;
+            *last_tap = None;
+        } else {
+            *last_tap = Some(now);
+        }
+    }
+
+    for touch in touches.iter_just_canceled() {
+        press_times.remove(&touch.id());
+    }
+}